@@ -0,0 +1,47 @@
+use decision::btree::Tree;
+use decision::{DTreeBuilder, Decision};
+use polars::prelude::*;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// renders the iris trees and the binary tree example as svg without
+// Graphviz, into the directory given as argument or target/figures; the
+// published figures in doc/images are still made with `dot`
+fn main() -> PolarsResult<()> {
+    let output = PathBuf::from(env::args().nth(1).unwrap_or_else(|| "target/figures".into()));
+    fs::create_dir_all(&output)?;
+    let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
+    let target = "variety";
+
+    let mut data = CsvReader::from_path("src/iris.csv")?.has_header(true).finish()?;
+    data.try_apply(target, |s| {
+        s.cast(&DataType::Categorical(None, CategoricalOrdering::Lexical))
+    })?;
+
+    // the same trees as examples/iris_trees.rs
+    let builders = [
+        DTreeBuilder::new(features.clone(), target).set_max_level(7),
+        DTreeBuilder::new(features.clone(), target)
+            .set_reuse_features(false)
+            .set_max_level(7),
+        DTreeBuilder::new(features.clone(), target).set_max_level(3),
+    ];
+    for (i, builder) in builders.iter().enumerate() {
+        let tree = builder.build(&data)?;
+        let path = output.join(format!("iris{}.svg", i + 1));
+        fs::write(&path, tree.svg_dump_with("yes", "no", Decision::label_lines))?;
+        println!("wrote {}", path.display());
+    }
+
+    // the same insertion order as examples/tree_plot.rs
+    let mut example: Tree<i64> = Tree::new();
+    for num in [6, 1, 0, 2, 5, 4, 9, 8, 3, 7] {
+        example.insert(num);
+    }
+    let path = output.join("tree_example.svg");
+    fs::write(&path, example.svg_dump("<", ">"))?;
+    println!("wrote {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use decision::DTreeBuilder;
+use polars::prelude::*;
+use std::collections::HashSet;
+use std::fs;
+
+// trains the three iris trees shown in the post and writes their graphs
+// next to the dataset, as src/iris1.dot, src/iris2.dot and src/iris3.dot
+fn main() -> PolarsResult<()> {
+    let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
+    let target = "variety";
+
+    let mut data = CsvReader::from_path("src/iris.csv")?.has_header(true).finish()?;
+    data.try_apply(target, |s| {
+        s.cast(&DataType::Categorical(None, CategoricalOrdering::Lexical))
+    })?;
+
+    let builders = [
+        DTreeBuilder::new(features.clone(), target).set_max_level(7),
+        DTreeBuilder::new(features.clone(), target)
+            .set_reuse_features(false)
+            .set_max_level(7),
+        DTreeBuilder::new(features.clone(), target).set_max_level(3),
+    ];
+    for (i, builder) in builders.iter().enumerate() {
+        let tree = builder.build(&data)?;
+        let path = format!("src/iris{}.dot", i + 1);
+        fs::write(&path, tree.dot_dump("yes", "no"))?;
+        println!("wrote {}", path);
+    }
+    Ok(())
+}
@@ -1,7 +1,15 @@
 #!/bin/bash
 cd $(dirname $0)
 cd ..
-dot src/iris1.dot -Tsvg -odoc/images/iris1.svg
-dot src/iris2.dot -Tsvg -odoc/images/iris2.svg
-dot src/iris3.dot -Tsvg -odoc/images/iris3.svg
-dot examples/example.dot -Tsvg -odoc/images/tree_example.svg
+# the iris graphs are written by the example, not by the cli
+cargo run --example iris_trees
+if command -v dot > /dev/null; then
+    dot src/iris1.dot -Tsvg -odoc/images/iris1.svg
+    dot src/iris2.dot -Tsvg -odoc/images/iris2.svg
+    dot src/iris3.dot -Tsvg -odoc/images/iris3.svg
+    dot examples/example.dot -Tsvg -odoc/images/tree_example.svg
+else
+    # without Graphviz, e.g. in CI, render natively into target/figures
+    # and leave the published figures alone
+    cargo run --example figures -- target/figures
+fi
@@ -0,0 +1,449 @@
+// Compact binary model format
+//
+// All integers are little endian. The file starts with a 16 bytes header
+//
+//   magic "DTRB" | version u32 | checksum u32 | payload length u32
+//
+// followed by the payload, whose checksum is the 32 bits FNV-1a hash:
+//
+//   string count u32 | feature count u32 | class count u32 | tree count u32
+//   target string u32
+//   string table: (offset u32, length u32) for each string
+//   string blob length u32 | utf-8 string blob
+//   feature strings: u32 each, in the order expected by `predict_row`
+//   class strings: u32 each
+//   trees: (first node u32, node count u32) for each tree
+//   nodes: 32 bytes records
+//     feature u32 (LEAF for leaves) | higher child u32 | lower child u32
+//     | predicted class u32 | cutoff f64 | confidence f64
+//
+// child indices are relative to the first node of their tree and always
+// greater than the index of their parent, so that a walk must terminate.
+
+use crate::btree::Node;
+use crate::model::Model;
+use crate::Decision;
+use memmap2::Mmap;
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"DTRB";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 16;
+const NODE_SIZE: usize = 32;
+const LEAF: u32 = u32::MAX;
+
+// 32 bits FNV-1a hash
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+// collects strings once, handing out their index
+#[derive(Default)]
+struct Interner {
+    strings: Vec<String>,
+    index: HashMap<String, u32>,
+}
+
+impl Interner {
+    fn intern(&mut self, text: &str) -> u32 {
+        if let Some(id) = self.index.get(text) {
+            return *id;
+        }
+        let id = self.strings.len() as u32;
+        self.strings.push(text.to_string());
+        self.index.insert(text.to_string(), id);
+        id
+    }
+}
+
+struct FlatNode {
+    feature: u32,
+    higher: u32,
+    lower: u32,
+    class: u32,
+    cutoff: f64,
+    confidence: f64,
+}
+
+// appends the nodes in pre-order so that children follow their parent
+fn flatten(
+    node: &Node<Decision>,
+    features: &HashMap<&str, u32>,
+    classes: &HashMap<&str, u32>,
+    nodes: &mut Vec<FlatNode>,
+) -> PolarsResult<u32> {
+    let index = nodes.len();
+    let class = *classes
+        .get(node.value.prediction())
+        .ok_or_else(|| polars_err!(ComputeError: "unknown class `{}`", node.value.prediction()))?;
+    nodes.push(FlatNode {
+        feature: LEAF,
+        higher: 0,
+        lower: 0,
+        class,
+        cutoff: 0.0,
+        confidence: node.value.confidence(),
+    });
+    if let (Some(rule), Some(left), Some(right)) = (node.value.rule(), &node.left, &node.right) {
+        let feature = *features
+            .get(rule.dimension())
+            .ok_or_else(|| polars_err!(ColumnNotFound: "the tree splits on unknown feature `{}`", rule.dimension()))?;
+        let higher = flatten(left, features, classes, nodes)?;
+        let lower = flatten(right, features, classes, nodes)?;
+        let flat = &mut nodes[index];
+        flat.feature = feature;
+        flat.higher = higher;
+        flat.lower = lower;
+        flat.cutoff = rule.cutoff();
+    }
+    Ok(index as u32)
+}
+
+/// Writes trees sharing the same target, features and classes as one
+/// compact binary file, scored by majority vote when there are many
+pub fn ensemble_to_binary(models: &[Model]) -> PolarsResult<Vec<u8>> {
+    let first = models
+        .first()
+        .ok_or_else(|| polars_err!(ComputeError: "cannot write an empty ensemble"))?;
+    for model in models {
+        polars_ensure!(
+            model.target == first.target && model.features == first.features && model.classes == first.classes,
+            SchemaMismatch: "all the trees of an ensemble must share target, features and classes"
+        );
+    }
+    let mut strings = Interner::default();
+    let target = strings.intern(&first.target);
+    let feature_ids: Vec<u32> = first.features.iter().map(|f| strings.intern(f)).collect();
+    let class_ids: Vec<u32> = first.classes.iter().map(|c| strings.intern(c)).collect();
+    let features: HashMap<&str, u32> = first.features.iter().enumerate().map(|(i, f)| (f.as_str(), i as u32)).collect();
+    let classes: HashMap<&str, u32> = first.classes.iter().enumerate().map(|(i, c)| (c.as_str(), i as u32)).collect();
+
+    let mut trees: Vec<(u32, u32)> = Vec::new();
+    let mut nodes: Vec<FlatNode> = Vec::new();
+    for model in models {
+        let mut tree_nodes = Vec::new();
+        if let Some(root) = model.tree.root() {
+            flatten(root, &features, &classes, &mut tree_nodes)?;
+        }
+        trees.push((nodes.len() as u32, tree_nodes.len() as u32));
+        nodes.extend(tree_nodes);
+    }
+
+    let mut payload: Vec<u8> = Vec::new();
+    put(&mut payload, strings.strings.len() as u32);
+    put(&mut payload, feature_ids.len() as u32);
+    put(&mut payload, class_ids.len() as u32);
+    put(&mut payload, trees.len() as u32);
+    put(&mut payload, target);
+    let mut offset = 0u32;
+    for text in &strings.strings {
+        put(&mut payload, offset);
+        put(&mut payload, text.len() as u32);
+        offset += text.len() as u32;
+    }
+    put(&mut payload, offset);
+    for text in &strings.strings {
+        payload.extend_from_slice(text.as_bytes());
+    }
+    for id in feature_ids.iter().chain(&class_ids) {
+        put(&mut payload, *id);
+    }
+    for (first, count) in &trees {
+        put(&mut payload, *first);
+        put(&mut payload, *count);
+    }
+    for node in &nodes {
+        put(&mut payload, node.feature);
+        put(&mut payload, node.higher);
+        put(&mut payload, node.lower);
+        put(&mut payload, node.class);
+        payload.extend_from_slice(&node.cutoff.to_le_bytes());
+        payload.extend_from_slice(&node.confidence.to_le_bytes());
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&checksum(&payload).to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+impl Model {
+    /// Writes the model in the compact binary format
+    pub fn to_binary(&self) -> PolarsResult<Vec<u8>> {
+        ensemble_to_binary(std::slice::from_ref(self))
+    }
+
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> PolarsResult<()> {
+        fs::write(path, self.to_binary()?)?;
+        Ok(())
+    }
+}
+
+fn put(payload: &mut Vec<u8>, value: u32) {
+    payload.extend_from_slice(&value.to_le_bytes());
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_f64(data: &[u8], offset: usize) -> f64 {
+    f64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// A model scored directly from its binary representation, either an
+/// owned buffer or a memory-mapped file
+pub struct CompactModel<B: AsRef<[u8]>> {
+    data: B,
+    target: String,
+    features: Vec<String>,
+    classes: Vec<String>,
+    trees: Vec<(usize, usize)>,
+    // absolute offset of the first node record
+    nodes: usize,
+}
+
+impl CompactModel<Mmap> {
+    /// Maps a binary model file in memory
+    pub fn open<P: AsRef<Path>>(path: P) -> PolarsResult<CompactModel<Mmap>> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read only; the file must not be
+        // truncated or rewritten while the model is in use
+        let data = unsafe { Mmap::map(&file)? };
+        CompactModel::from_bytes(data)
+    }
+}
+
+impl<B: AsRef<[u8]>> CompactModel<B> {
+    /// Validates the header, checksum and all node references
+    pub fn from_bytes(data: B) -> PolarsResult<CompactModel<B>> {
+        let bytes = data.as_ref();
+        polars_ensure!(
+            bytes.len() >= HEADER_SIZE && &bytes[0..4] == MAGIC,
+            ComputeError: "not a binary decision tree model"
+        );
+        let version = read_u32(bytes, 4);
+        polars_ensure!(
+            version == VERSION,
+            ComputeError: "binary model version {} is not supported, this library reads version {}", version, VERSION
+        );
+        let length = read_u32(bytes, 12) as usize;
+        polars_ensure!(
+            bytes.len() == HEADER_SIZE + length,
+            ComputeError: "binary model is truncated"
+        );
+        let payload = &bytes[HEADER_SIZE..];
+        polars_ensure!(
+            checksum(payload) == read_u32(bytes, 8),
+            ComputeError: "binary model checksum mismatch"
+        );
+
+        let truncated = || polars_err!(ComputeError: "binary model is truncated");
+        let mut cursor = 0usize;
+        let mut next = |count: usize| -> PolarsResult<usize> {
+            let start = cursor;
+            cursor = cursor.checked_add(count).filter(|end| *end <= payload.len()).ok_or_else(truncated)?;
+            Ok(start)
+        };
+        let counts = next(20)?;
+        let string_count = read_u32(payload, counts) as usize;
+        let feature_count = read_u32(payload, counts + 4) as usize;
+        let class_count = read_u32(payload, counts + 8) as usize;
+        let tree_count = read_u32(payload, counts + 12) as usize;
+        let target = read_u32(payload, counts + 16) as usize;
+
+        let table = next(string_count.checked_mul(8).ok_or_else(truncated)?)?;
+        let blob_length = read_u32(payload, next(4)?) as usize;
+        let blob = next(blob_length)?;
+        let strings: Vec<&str> = (0..string_count)
+            .map(|i| {
+                let offset = read_u32(payload, table + 8 * i) as usize;
+                let length = read_u32(payload, table + 8 * i + 4) as usize;
+                let end = offset.checked_add(length).filter(|end| *end <= blob_length).ok_or_else(truncated)?;
+                std::str::from_utf8(&payload[blob + offset..blob + end])
+                    .map_err(|_| polars_err!(ComputeError: "binary model holds an invalid string"))
+            })
+            .collect::<PolarsResult<_>>()?;
+        let string = |id: u32| -> PolarsResult<String> {
+            strings
+                .get(id as usize)
+                .map(|s| s.to_string())
+                .ok_or_else(|| polars_err!(ComputeError: "binary model refers to a missing string"))
+        };
+        let target = string(target as u32)?;
+        let ids = next(feature_count.checked_add(class_count).and_then(|n| n.checked_mul(4)).ok_or_else(truncated)?)?;
+        let features: Vec<String> = (0..feature_count)
+            .map(|i| string(read_u32(payload, ids + 4 * i)))
+            .collect::<PolarsResult<_>>()?;
+        let classes: Vec<String> = (0..class_count)
+            .map(|i| string(read_u32(payload, ids + 4 * (feature_count + i))))
+            .collect::<PolarsResult<_>>()?;
+        let tree_table = next(tree_count.checked_mul(8).ok_or_else(truncated)?)?;
+        let trees: Vec<(usize, usize)> = (0..tree_count)
+            .map(|i| {
+                (
+                    read_u32(payload, tree_table + 8 * i) as usize,
+                    read_u32(payload, tree_table + 8 * i + 4) as usize,
+                )
+            })
+            .collect();
+        let nodes = cursor;
+        polars_ensure!(
+            (payload.len() - nodes).is_multiple_of(NODE_SIZE),
+            ComputeError: "binary model node table is malformed"
+        );
+        let node_count = (payload.len() - nodes) / NODE_SIZE;
+        for (first, count) in &trees {
+            polars_ensure!(
+                first.checked_add(*count).is_some_and(|end| end <= node_count),
+                ComputeError: "binary model tree exceeds the node table"
+            );
+            for i in 0..*count {
+                let record = nodes + NODE_SIZE * (first + i);
+                let feature = read_u32(payload, record);
+                polars_ensure!(
+                    (read_u32(payload, record + 12) as usize) < class_count,
+                    ComputeError: "binary model node refers to a missing class"
+                );
+                if feature != LEAF {
+                    let higher = read_u32(payload, record + 4) as usize;
+                    let lower = read_u32(payload, record + 8) as usize;
+                    polars_ensure!(
+                        (feature as usize) < feature_count
+                            && higher > i && higher < *count
+                            && lower > i && lower < *count,
+                        ComputeError: "binary model node has invalid references"
+                    );
+                }
+            }
+        }
+        Ok(CompactModel {
+            data,
+            target,
+            features,
+            classes,
+            trees,
+            nodes: HEADER_SIZE + nodes,
+        })
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Feature names in the order expected by `predict_row`
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    pub fn classes(&self) -> &[String] {
+        &self.classes
+    }
+
+    pub fn tree_count(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// Class index and confidence of the leaf reached in a single tree;
+    /// missing values, given as NaN, follow the `<=` branch
+    pub fn predict_tree(&self, tree: usize, row: &[f64]) -> Option<(usize, f64)> {
+        let (first, count) = *self.trees.get(tree)?;
+        if count == 0 {
+            return None;
+        }
+        let bytes = self.data.as_ref();
+        let mut index = 0usize;
+        loop {
+            let record = self.nodes + NODE_SIZE * (first + index);
+            let feature = read_u32(bytes, record);
+            if feature == LEAF {
+                return Some((read_u32(bytes, record + 12) as usize, read_f64(bytes, record + 24)));
+            }
+            let value = row.get(feature as usize).copied().unwrap_or(f64::NAN);
+            index = if value > read_f64(bytes, record + 16) {
+                read_u32(bytes, record + 4)
+            } else {
+                read_u32(bytes, record + 8)
+            } as usize;
+        }
+    }
+
+    /// Class index voted by the majority of the trees
+    pub fn predict_row(&self, row: &[f64]) -> Option<usize> {
+        let mut votes = vec![0usize; self.classes.len()];
+        for tree in 0..self.trees.len() {
+            if let Some((class, _)) = self.predict_tree(tree, row) {
+                votes[class] += 1;
+            }
+        }
+        let best = votes.iter().copied().max().filter(|v| *v > 0)?;
+        votes.iter().position(|v| *v == best)
+    }
+
+    /// Predicts the class of each row of the dataframe
+    pub fn predict(&self, data: &DataFrame) -> PolarsResult<Series> {
+        let columns: Vec<Vec<f64>> = self
+            .features
+            .iter()
+            .map(|feature| {
+                Ok(data
+                    .column(feature)?
+                    .cast(&DataType::Float64)?
+                    .f64()?
+                    .into_iter()
+                    .map(|v| v.unwrap_or(f64::NAN))
+                    .collect())
+            })
+            .collect::<PolarsResult<_>>()?;
+        let mut row = vec![0.0; columns.len()];
+        let predictions: Vec<Option<&str>> = (0..data.height())
+            .map(|i| {
+                for (value, column) in row.iter_mut().zip(&columns) {
+                    *value = column[i];
+                }
+                self.predict_row(&row).map(|class| self.classes[class].as_str())
+            })
+            .collect();
+        Ok(Series::new("prediction", predictions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use std::collections::HashSet;
+
+    #[test]
+    fn binary_round_trip() {
+        let data = iris();
+        let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
+        let model = DTreeBuilder::new(features, "variety")
+            .set_max_level(4)
+            .fit(&data)
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("decision-{}.dtrb", std::process::id()));
+        model.save_binary(&path).unwrap();
+        let mapped = CompactModel::open(&path).unwrap();
+        assert_eq!(mapped.target(), "variety");
+        assert_eq!(mapped.features(), model.features.as_slice());
+        assert_eq!(mapped.classes(), model.classes.as_slice());
+        assert!(mapped.predict(&data).unwrap().equals(&model.predict(&data).unwrap()));
+        fs::remove_file(&path).unwrap();
+
+        let mut bytes = model.to_binary().unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        let error = CompactModel::from_bytes(bytes).err().unwrap().to_string();
+        assert!(error.contains("checksum"));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::btree::{Node, Tree};
+use crate::plot::{color, Plot};
+use crate::Decision;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+/// An axis-aligned rectangle of the plane spanned by two features in which
+/// the tree predicts a single class
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub lower: (f64, f64),
+    pub upper: (f64, f64),
+    pub prediction: String,
+}
+
+// splits the rectangle of `node` along its rule until the leaves are reached;
+// rules on other features follow the branch of their fixed value
+fn split_region(
+    node: &Node<Decision>,
+    features: (&str, &str),
+    fixed: &HashMap<&str, f64>,
+    lower: (f64, f64),
+    upper: (f64, f64),
+    regions: &mut Vec<Region>,
+) -> PolarsResult<()> {
+    if lower.0 >= upper.0 || lower.1 >= upper.1 {
+        return Ok(());
+    }
+    let (rule, left, right) = match (node.value.rule(), &node.left, &node.right) {
+        (Some(rule), Some(left), Some(right)) => (rule, left, right),
+        _ => {
+            regions.push(Region {
+                lower,
+                upper,
+                prediction: node.value.prediction().to_string(),
+            });
+            return Ok(());
+        }
+    };
+    let cutoff = rule.cutoff();
+    if rule.dimension() == features.0 {
+        split_region(left, features, fixed, (lower.0.max(cutoff), lower.1), upper, regions)?;
+        split_region(right, features, fixed, lower, (upper.0.min(cutoff), upper.1), regions)
+    } else if rule.dimension() == features.1 {
+        split_region(left, features, fixed, (lower.0, lower.1.max(cutoff)), upper, regions)?;
+        split_region(right, features, fixed, lower, (upper.0, upper.1.min(cutoff)), regions)
+    } else {
+        let value = fixed.get(rule.dimension()).ok_or_else(|| {
+            polars_err!(ComputeError: "feature `{}` is split on but neither plotted nor fixed", rule.dimension())
+        })?;
+        let child = if *value > cutoff { left } else { right };
+        split_region(child, features, fixed, lower, upper, regions)
+    }
+}
+
+/// The decision regions of a tree within the rectangle `lower`..`upper` of
+/// two features, read off the split thresholds; every other feature the tree
+/// splits on must be given a value in `fixed`
+pub fn decision_regions(
+    tree: &Tree<Decision>,
+    features: (&str, &str),
+    fixed: &HashMap<&str, f64>,
+    lower: (f64, f64),
+    upper: (f64, f64),
+) -> PolarsResult<Vec<Region>> {
+    let root = tree
+        .root()
+        .ok_or_else(|| polars_err!(ComputeError: "cannot plot an empty tree"))?;
+    let mut regions = Vec::new();
+    split_region(root, features, fixed, lower, upper, &mut regions)?;
+    Ok(regions)
+}
+
+/// Renders the training data of two features as an svg scatter plot over the
+/// decision regions of the tree, both colored by class
+pub fn decision_boundary_plot(
+    tree: &Tree<Decision>,
+    data: &DataFrame,
+    target: &str,
+    features: (&str, &str),
+    fixed: &HashMap<&str, f64>,
+) -> PolarsResult<String> {
+    let x = data.column(features.0)?.cast(&DataType::Float64)?;
+    let y = data.column(features.1)?.cast(&DataType::Float64)?;
+    let labels = data.column(target)?.cast(&DataType::String)?;
+    let range = |values: &Series| -> PolarsResult<(f64, f64)> {
+        let values = values.f64()?;
+        match (values.min(), values.max()) {
+            // a little room so that the outermost points are not on the frame
+            (Some(low), Some(high)) => Ok((low - 0.05 * (high - low), high + 0.05 * (high - low))),
+            _ => polars_bail!(ComputeError: "feature `{}` has no values", values.name()),
+        }
+    };
+    let (x_range, y_range) = (range(&x)?, range(&y)?);
+    let regions = decision_regions(tree, features, fixed, (x_range.0, y_range.0), (x_range.1, y_range.1))?;
+
+    let classes = tree.classes();
+    let class_color = |class: &str| color(classes.iter().position(|c| c == class).unwrap_or(classes.len()));
+    let mut plot = Plot::new(x_range, y_range);
+    for region in &regions {
+        plot.rect(region.lower, region.upper, class_color(&region.prediction), 0.25);
+    }
+    for ((x, y), label) in x.f64()?.into_iter().zip(y.f64()?).zip(labels.str()?) {
+        if let (Some(x), Some(y), Some(label)) = (x, y, label) {
+            plot.circle((x, y), 3.0, class_color(label), 0.9);
+        }
+    }
+    plot.axes(features.0, features.1);
+    let entries: Vec<(&str, &str)> = classes.iter().map(|c| (class_color(c), c.as_str())).collect();
+    plot.legend(&entries);
+    Ok(plot.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+
+    #[test]
+    fn decision_regions_tile_the_plane() {
+        let data = iris();
+        let features = ("petal_length", "petal_width");
+        let tree = DTreeBuilder::new([features.0, features.1], "variety")
+            .set_max_level(3)
+            .build(&data)
+            .unwrap();
+        let (lower, upper) = ((0.0, 0.0), (8.0, 3.0));
+        let regions = decision_regions(&tree, features, &HashMap::new(), lower, upper).unwrap();
+        let area: f64 = regions
+            .iter()
+            .map(|r| (r.upper.0 - r.lower.0) * (r.upper.1 - r.lower.1))
+            .sum();
+        assert!((area - 24.0).abs() < 1e-9);
+        for region in &regions {
+            let center = ((region.lower.0 + region.upper.0) / 2.0, (region.lower.1 + region.upper.1) / 2.0);
+            let leaf = tree
+                .leaf(|feature| Some(if feature == features.0 { center.0 } else { center.1 }))
+                .unwrap();
+            assert_eq!(leaf.prediction(), region.prediction);
+        }
+        let svg = decision_boundary_plot(&tree, &data, "variety", features, &HashMap::new()).unwrap();
+        assert_eq!(svg.matches("<circle").count(), 150);
+        assert_eq!(svg.matches("fill-opacity=\"0.25\"").count(), regions.len());
+
+        // other split features need a fixed value
+        let all = ["sepal_length", "sepal_width", "petal_length", "petal_width"];
+        let tree = DTreeBuilder::new(all, "variety").set_max_level(3).build(&data).unwrap();
+        let sepals = ("sepal_length", "sepal_width");
+        let fixed = HashMap::from([("petal_length", 4.0), ("petal_width", 1.2)]);
+        let regions = decision_regions(&tree, sepals, &fixed, (4.0, 2.0), (8.0, 4.5)).unwrap();
+        assert!(regions.iter().all(|r| r.prediction == "Versicolor"));
+        assert!(decision_regions(&tree, sepals, &HashMap::new(), (4.0, 2.0), (8.0, 4.5)).is_err());
+    }
+}
@@ -1,7 +1,12 @@
+use std::collections::HashSet;
 use std::fmt::{Debug, Display};
-mod dot;
+pub mod dot;
+mod mermaid;
+mod plantuml;
+mod svg;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node<T> {
     pub value: T,
     pub left: Option<Box<Node<T>>>,
@@ -25,6 +30,7 @@ impl<T> From<Node<T>> for Option<Box<Node<T>>> {
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tree<T> {
     root: Option<Box<Node<T>>>,
 }
@@ -40,6 +46,10 @@ impl<T> Tree<T> {
         }
     }
 
+    pub fn root(&self) -> Option<&Node<T>> {
+        self.root.as_deref()
+    }
+
     pub fn post_order_iter<'a>(& 'a self) -> PostOrderTraversalIter<'a, T>{
         PostOrderTraversalIter::new(self)
     }
@@ -197,6 +207,39 @@ impl<'a, T> Iterator for PreOrderTraversalIter<'a, T>{
 
 impl<T: Display> Tree<T>{
     pub fn dot_dump(&self, left: &str, right: &str) -> String{
+        self.dot_graph(left, right).to_string()
+    }
+
+    // same as `dot_dump`, with the nodes of `path` (heap ids) and their edges highlighted
+    pub fn dot_dump_highlighted(&self, left: &str, right: &str, path: &HashSet<usize>, style: &dot::Highlight) -> String{
+        let mut graph = self.dot_graph(left, right);
+        graph.highlight(&path.iter().map(|id| format!("node{}",id)).collect(), style);
+        graph.to_string()
+    }
+
+    /// Renders the tree as an SVG document with a tidy top-down layout,
+    /// without needing Graphviz; labels are split on line breaks
+    pub fn svg_dump(&self, left: &str, right: &str) -> String{
+        self.svg_dump_with(left, right, |value| value.to_string().lines().map(String::from).collect())
+    }
+
+    /// Same as `svg_dump`, with `lines` giving the lines of each label
+    pub fn svg_dump_with<F: Fn(&T) -> Vec<String>>(&self, left: &str, right: &str, lines: F) -> String{
+        svg::render(self.root(), left, right, &lines)
+    }
+
+    /// Renders the tree as a Mermaid flowchart, for Markdown documents
+    pub fn mermaid_dump(&self, left: &str, right: &str) -> String{
+        mermaid::render(self, left, right)
+    }
+
+    /// Renders the tree as a PlantUML diagram of linked rectangles
+    pub fn plantuml_dump(&self, left: &str, right: &str) -> String{
+        plantuml::render(self, left, right)
+    }
+
+    /// The graph rendered by `dot_dump`, open to further changes
+    pub fn dot_graph(&self, left: &str, right: &str) -> dot::Dot{
         let mut graph =  dot::Dot::new();
         let shape = "box";
         let branch_style = "filled";
@@ -208,24 +251,22 @@ impl<T: Display> Tree<T>{
             let name = format!("node{}",item.id);
             let parent_name = format!("node{}",item.id >> 1);
             let label = item.value.to_string();
-            graph.add_node(
-                name.clone(),
-                label,
-                if item.leaf {leaf_shape.to_owned()} else {shape.to_owned()},
-                if item.leaf {Some(leaf_style.to_owned())} else {Some(branch_style.to_owned())},
-                if item.leaf {Some(leaf_color.to_owned())} else {Some(branch_color.to_owned())},
-            );
+            graph.node(&name)
+                .set("label", label)
+                .set("shape", if item.leaf {leaf_shape} else {shape})
+                .set("style", if item.leaf {leaf_style} else {branch_style})
+                .set("fillcolor", if item.leaf {leaf_color} else {branch_color});
             if item.id > 1 {
-                let edgelabel : String= if item.id % 2 == 0{
-                    left.to_owned()
+                let edgelabel = if item.id % 2 == 0{
+                    left
                 }else{
-                    right.to_owned()
+                    right
                 };
-                graph.add_edge(parent_name, name.clone(),edgelabel);
+                graph.edge(&parent_name, &name).set("label", edgelabel);
             }
-            graph.append_rank(item.level - 1, name);
+            graph.append_rank(item.level - 1, &name);
         }
-        graph.to_string()
+        graph
     }
 }
 
@@ -1,108 +1,478 @@
 use std::fmt;
 use std::fmt::Display;
-use core::default::Default;
-use std::mem::{take, replace};
+use std::collections::HashSet;
 
-struct DotNode{
-    name: String,
-    label: String,
-    shape: String,
-    style: Option<String>,
-    fillcolor: Option<String>
+/// Value of a node, edge or graph attribute
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    // quoted string; quotes and line breaks are escaped while Graphviz
+    // escape sequences such as `\n` or `\l` are kept
+    Text(String),
+    // HTML-like label, written between angle brackets
+    Html(String),
+    // written verbatim, for numbers and plain identifiers
+    Id(String),
 }
 
-impl Display for DotNode{
+impl From<&str> for AttributeValue {
+    fn from(text: &str) -> Self {
+        AttributeValue::Text(text.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(text: String) -> Self {
+        AttributeValue::Text(text)
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        AttributeValue::Id(value.to_string())
+    }
+}
+
+impl From<usize> for AttributeValue {
+    fn from(value: usize) -> Self {
+        AttributeValue::Id(value.to_string())
+    }
+}
+
+// backslash sequences understood by Graphviz inside labels
+const ESCAPES: [char; 10] = ['n', 'l', 'r', 'N', 'G', 'E', 'H', 'T', 'L', '\\'];
+
+// keywords of the DOT language, which are case insensitive
+const KEYWORDS: [&str; 6] = ["node", "edge", "graph", "digraph", "subgraph", "strict"];
+
+/// Escapes text for a quoted DOT string; Graphviz escape sequences and
+/// escaped backslashes are kept, any other backslash is escaped
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next_if(|next| ESCAPES.contains(next)) {
+                Some(next) => {
+                    escaped.push('\\');
+                    escaped.push(next);
+                }
+                None => escaped.push_str("\\\\"),
+            },
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// node names are written verbatim when they are plain identifiers or numbers
+fn identifier(name: &str) -> String {
+    let plain = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let number = !name.is_empty() && name.parse::<f64>().is_ok() && name.chars().all(|c| c.is_ascii_digit() || c == '.' || c == '-');
+    let keyword = KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(name));
+    if (plain && !keyword) || number {
+        name.to_string()
+    } else {
+        format!("\"{}\"", escape(name))
+    }
+}
+
+impl Display for AttributeValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let style: String = match self.style {
-            None => "".into(),
-            Some(ref kind) => format!(", style=\"{}\"",kind)
-        };
-        let fillcolor: String = match self.fillcolor {
-            None => "".into(),
-            Some(ref kind) => format!(", fillcolor=\"{}\"",kind)
-        };
-        write!(f,"{} [label=\"{}\", shape=\"{}\"{}{}];",self.name, self.label, self.shape, style, fillcolor)
+        match self {
+            AttributeValue::Text(text) => write!(f, "\"{}\"", escape(text)),
+            AttributeValue::Html(html) => write!(f, "<{}>", html),
+            AttributeValue::Id(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+/// Ordered attribute list, setting a key again replaces its value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attributes(Vec<(String, AttributeValue)>);
+
+impl Attributes {
+    pub fn set<V: Into<AttributeValue>>(&mut self, key: &str, value: V) {
+        let value = value.into();
+        match self.0.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.0.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&AttributeValue> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Display for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let entries: Vec<String> = self.0.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        write!(f, "{}", entries.join(", "))
     }
 }
 
-struct DotEdge{
-    first: String,
-    second: String,
-    label: String
+/// Attributes set by `highlight`: `node` and `edge` on the named nodes and
+/// the edges joining them, `faded_node` and `faded_edge` on everything else;
+/// a `style` is added to the existing style list and a `penwidth` never
+/// makes a line thinner
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Highlight {
+    pub node: Attributes,
+    pub edge: Attributes,
+    pub faded_node: Attributes,
+    pub faded_edge: Attributes,
+}
+
+// sets the attributes of `style` on `attributes` following the rules of `Highlight`
+fn apply(attributes: &mut Attributes, style: &Attributes) {
+    for (key, value) in &style.0 {
+        match (key.as_str(), attributes.get(key), value) {
+            ("style", Some(AttributeValue::Text(old)), AttributeValue::Text(new)) => {
+                let merged = format!("{},{}", old, new);
+                attributes.set(key, merged)
+            }
+            ("penwidth", Some(AttributeValue::Id(old)), AttributeValue::Id(new))
+                if old.parse::<f64>().ok() >= new.parse::<f64>().ok() => {}
+            _ => attributes.set(key, value.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DotNode {
+    pub name: String,
+    pub attributes: Attributes,
+}
+
+impl DotNode {
+    pub fn set<V: Into<AttributeValue>>(&mut self, key: &str, value: V) -> &mut DotNode {
+        self.attributes.set(key, value);
+        self
+    }
 }
 
-impl Display for DotEdge{
+impl Display for DotNode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f,"{} -> {} [label=\"{}\"]",self.first,self.second,self.label)
+        if self.attributes.is_empty() {
+            write!(f, "{};", identifier(&self.name))
+        } else {
+            write!(f, "{} [{}];", identifier(&self.name), self.attributes)
+        }
     }
 }
 
-#[derive(Default)]
-struct DotRank(Vec<String>);
-impl DotRank{
-    fn new() -> DotRank{
-        DotRank(
-            Vec::new()
-        )
+#[derive(Debug, Clone)]
+pub struct DotEdge {
+    pub first: String,
+    pub second: String,
+    pub attributes: Attributes,
+}
+
+impl DotEdge {
+    pub fn set<V: Into<AttributeValue>>(&mut self, key: &str, value: V) -> &mut DotEdge {
+        self.attributes.set(key, value);
+        self
     }
 }
 
-impl Display for DotRank{
+impl Display for DotEdge {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f,"{{rank = same; {};}}",self.0.join("; "))
+        write!(f, "{} -> {}", identifier(&self.first), identifier(&self.second))?;
+        if !self.attributes.is_empty() {
+            write!(f, " [{}]", self.attributes)?;
+        }
+        Ok(())
     }
 }
 
-pub struct Dot{
+// nodes drawn at the same level
+#[derive(Debug, Clone, Default)]
+struct DotRank(Vec<String>);
+
+impl Display for DotRank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<String> = self.0.iter().map(|n| identifier(n)).collect();
+        write!(f, "{{rank = same; {};}}", names.join("; "))
+    }
+}
+
+/// A group of nodes and edges; clusters are drawn inside a box
+#[derive(Debug, Clone, Default)]
+pub struct Subgraph {
+    name: Option<String>,
+    pub attributes: Attributes,
     nodes: Vec<DotNode>,
     edges: Vec<DotEdge>,
-    ranks: Vec<DotRank>
+    ranks: Vec<DotRank>,
+    subgraphs: Vec<Subgraph>,
 }
 
-impl Dot{
-    pub fn new() -> Self{
-        Dot{
-            nodes: Vec::new(),
-            edges: Vec::new(),
-            ranks: Vec::new(),
+impl Subgraph {
+    pub fn new(name: &str) -> Subgraph {
+        Subgraph {
+            name: Some(name.to_string()),
+            ..Subgraph::default()
         }
     }
 
-    pub fn add_node(& mut self, name: String, label: String, shape: String, style: Option<String>, fillcolor: Option<String>) -> () {
-        let node = DotNode{name, label, shape, style, fillcolor};
-        self.nodes.push(node);
+    // Graphviz only boxes subgraphs whose name starts with `cluster`
+    pub fn cluster(name: &str) -> Subgraph {
+        Subgraph::new(&format!("cluster_{}", name))
     }
 
-    pub fn add_edge(& mut self, first: String, second: String, label: String) -> () {
-        let node = DotEdge{first, second, label};
-        self.edges.push(node);
+    pub fn set<V: Into<AttributeValue>>(&mut self, key: &str, value: V) -> &mut Subgraph {
+        self.attributes.set(key, value);
+        self
+    }
+
+    /// Adds a node, or returns the existing one with the same name
+    pub fn node(&mut self, name: &str) -> &mut DotNode {
+        match self.nodes.iter().position(|n| n.name == name) {
+            Some(index) => &mut self.nodes[index],
+            None => {
+                self.nodes.push(DotNode {
+                    name: name.to_string(),
+                    attributes: Attributes::default(),
+                });
+                self.nodes.last_mut().unwrap()
+            }
+        }
     }
 
-    pub fn append_rank(& mut self, index: usize, node: String) -> (){
-        //ensure space
+    pub fn edge(&mut self, first: &str, second: &str) -> &mut DotEdge {
+        self.edges.push(DotEdge {
+            first: first.to_string(),
+            second: second.to_string(),
+            attributes: Attributes::default(),
+        });
+        self.edges.last_mut().unwrap()
+    }
+
+    pub fn add_subgraph(&mut self, subgraph: Subgraph) -> &mut Subgraph {
+        self.subgraphs.push(subgraph);
+        self.subgraphs.last_mut().unwrap()
+    }
+
+    pub fn nodes(&self) -> &[DotNode] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> &[DotEdge] {
+        &self.edges
+    }
+
+    // places the node on the `index`-th rank, ranks are created as needed
+    pub fn append_rank(&mut self, index: usize, node: &str) {
         while self.ranks.len() <= index {
-            self.ranks.push(DotRank::new())
+            self.ranks.push(DotRank::default())
+        }
+        self.ranks[index].0.push(node.to_string());
+    }
+
+    // styles the named nodes and the edges joining them apart from the rest
+    pub fn highlight(&mut self, names: &HashSet<String>, style: &Highlight) {
+        for node in &mut self.nodes {
+            if names.contains(&node.name) {
+                apply(&mut node.attributes, &style.node);
+            } else {
+                apply(&mut node.attributes, &style.faded_node);
+            }
+        }
+        for edge in &mut self.edges {
+            if names.contains(&edge.first) && names.contains(&edge.second) {
+                apply(&mut edge.attributes, &style.edge);
+            } else {
+                apply(&mut edge.attributes, &style.faded_edge);
+            }
+        }
+        for subgraph in &mut self.subgraphs {
+            subgraph.highlight(names, style);
         }
-        let mut bin = take(& mut self.ranks[index]);
-        bin.0.push(node);
-        let _ = replace(& mut self.ranks[index], bin);
     }
 }
 
-impl Display for Dot{
+impl Display for Subgraph {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut graph: Vec<String>=vec!["digraph {".into(),"rankdir = BT;".into(),"subgraph{".into()];
-        for node in &self.nodes{
-            graph.push(node.to_string());
+        match self.name {
+            Some(ref name) => writeln!(f, "subgraph {} {{", identifier(name))?,
+            None => writeln!(f, "subgraph{{")?,
+        }
+        for (key, value) in &self.attributes.0 {
+            writeln!(f, "{}={};", key, value)?;
+        }
+        for node in &self.nodes {
+            writeln!(f, "{}", node)?;
         }
-        for edge in &self.edges{
-            graph.push(edge.to_string());
+        for edge in &self.edges {
+            writeln!(f, "{}", edge)?;
         }
-        for rank in &self.ranks{
-            graph.push(rank.to_string());
+        for rank in &self.ranks {
+            writeln!(f, "{}", rank)?;
+        }
+        for subgraph in &self.subgraphs {
+            writeln!(f, "{}", subgraph)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Direction in which ranks are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankDir {
+    TopBottom,
+    BottomTop,
+    LeftRight,
+    RightLeft,
+}
+
+impl Display for RankDir {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match self {
+            RankDir::TopBottom => "TB",
+            RankDir::BottomTop => "BT",
+            RankDir::LeftRight => "LR",
+            RankDir::RightLeft => "RL",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+/// A Graphviz digraph; nodes, edges and subgraphs live in its body
+#[derive(Debug, Clone)]
+pub struct Dot {
+    rankdir: RankDir,
+    pub attributes: Attributes,
+    // defaults applied to every node and edge
+    pub node_defaults: Attributes,
+    pub edge_defaults: Attributes,
+    pub body: Subgraph,
+}
+
+impl Default for Dot {
+    fn default() -> Self {
+        Dot::new()
+    }
+}
+
+impl Dot {
+    // trees grow upwards by default
+    pub fn new() -> Self {
+        Dot {
+            rankdir: RankDir::BottomTop,
+            attributes: Attributes::default(),
+            node_defaults: Attributes::default(),
+            edge_defaults: Attributes::default(),
+            body: Subgraph::default(),
+        }
+    }
+
+    pub fn set_rankdir(&mut self, rankdir: RankDir) -> &mut Dot {
+        self.rankdir = rankdir;
+        self
+    }
+
+    pub fn node(&mut self, name: &str) -> &mut DotNode {
+        self.body.node(name)
+    }
+
+    pub fn edge(&mut self, first: &str, second: &str) -> &mut DotEdge {
+        self.body.edge(first, second)
+    }
+
+    pub fn add_subgraph(&mut self, subgraph: Subgraph) -> &mut Subgraph {
+        self.body.add_subgraph(subgraph)
+    }
+
+    pub fn append_rank(&mut self, index: usize, node: &str) {
+        self.body.append_rank(index, node)
+    }
+
+    pub fn highlight(&mut self, names: &HashSet<String>, style: &Highlight) {
+        self.body.highlight(names, style)
+    }
+}
+
+impl Display for Dot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "digraph {{")?;
+        writeln!(f, "rankdir = {};", self.rankdir)?;
+        for (key, value) in &self.attributes.0 {
+            writeln!(f, "{}={};", key, value)?;
+        }
+        if !self.node_defaults.is_empty() {
+            writeln!(f, "node [{}];", self.node_defaults)?;
+        }
+        if !self.edge_defaults.is_empty() {
+            writeln!(f, "edge [{}];", self.edge_defaults)?;
+        }
+        writeln!(f, "{}", self.body)?;
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_with_clusters() {
+        let mut graph = Dot::new();
+        graph.set_rankdir(RankDir::LeftRight);
+        graph.attributes.set("label", "a \"quoted\"\ntitle");
+        graph.node_defaults.set("shape", "box");
+        graph.node("a").set("label", "first\\lline\\");
+        graph
+            .node("b c")
+            .set("label", AttributeValue::Html("<b>bold</b>".into()));
+        graph.edge("a", "b c").set("weight", 2usize);
+        let cluster = graph.add_subgraph(Subgraph::cluster("group"));
+        cluster.set("label", "group");
+        cluster.node("d");
+        cluster.edge("d", "a");
+        graph.append_rank(0, "a");
+        graph.append_rank(0, "b c");
+
+        let expected = [
+            "digraph {",
+            "rankdir = LR;",
+            "label=\"a \\\"quoted\\\"\\ntitle\";",
+            "node [shape=\"box\"];",
+            "subgraph{",
+            "a [label=\"first\\lline\\\\\"];",
+            "\"b c\" [label=<<b>bold</b>>];",
+            "a -> \"b c\" [weight=2]",
+            "{rank = same; a; \"b c\";}",
+            "subgraph cluster_group {",
+            "label=\"group\";",
+            "d;",
+            "d -> a",
+            "}",
+            "}",
+            "}",
+        ];
+        assert_eq!(graph.to_string(), expected.join("\n"));
+        assert_eq!(graph.node("a").attributes.get("label"), Some(&"first\\lline\\".into()));
+        assert_eq!(graph.body.nodes().len(), 2);
+    }
+
+    #[test]
+    fn escaping_and_keywords() {
+        assert_eq!(escape(r#"say \"hi\""#), r#"say \\\"hi\\\""#);
+        assert_eq!(escape("a\\lb\\\\c\\xd\r\n"), "a\\lb\\\\c\\\\xd\\n");
+        assert_eq!(escape("end\\"), "end\\\\");
+        for keyword in ["node", "Edge", "GRAPH", "digraph", "subgraph", "strict"] {
+            assert_eq!(identifier(keyword), format!("\"{}\"", keyword));
         }
-        graph.push("}".into());
-        graph.push("}".into());
-        write!(f,"{}",graph.join("\n"))
+        assert_eq!(identifier("nodes"), "nodes");
+        assert_eq!(AttributeValue::from(0.125).to_string(), "0.125");
+        assert_eq!(AttributeValue::from(3.0).to_string(), "3");
     }
 }
@@ -0,0 +1,64 @@
+use super::Tree;
+use std::fmt::Display;
+
+// Mermaid strings have no backslash escapes, quotes become entities
+fn text(label: &str) -> String {
+    label.replace('"', "#quot;").replace("\\n", "<br/>").replace('\n', "<br/>")
+}
+
+pub(super) fn render<T: Display>(tree: &Tree<T>, left: &str, right: &str) -> String {
+    let mut lines = vec!["flowchart TD".to_string()];
+    let mut edges = Vec::new();
+    let (mut leaves, mut branches) = (Vec::new(), Vec::new());
+    for item in tree.pre_order_iter() {
+        let name = format!("node{}", item.id);
+        let label = text(&item.value.to_string());
+        if item.leaf {
+            lines.push(format!("    {}([\"{}\"])", name, label));
+            leaves.push(name.clone());
+        } else {
+            lines.push(format!("    {}[\"{}\"]", name, label));
+            branches.push(name.clone());
+        }
+        if item.id > 1 {
+            let edgelabel = if item.id % 2 == 0 { left } else { right };
+            edges.push(format!("    node{} -->|\"{}\"| {}", item.id >> 1, text(edgelabel), name));
+        }
+    }
+    lines.extend(edges);
+    lines.push("    classDef branch fill:#fce283,stroke:#333".into());
+    lines.push("    classDef leaf fill:#95fc83,stroke:#333".into());
+    if !branches.is_empty() {
+        lines.push(format!("    class {} branch", branches.join(",")));
+    }
+    if !leaves.is_empty() {
+        lines.push(format!("    class {} leaf", leaves.join(",")));
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flowchart() {
+        let mut tree: Tree<i64> = Tree::new();
+        for num in [2, 1, 3] {
+            tree.insert(num);
+        }
+        let expected = [
+            "flowchart TD",
+            "    node1[\"2\"]",
+            "    node3([\"3\"])",
+            "    node2([\"1\"])",
+            "    node1 -->|\">#quot;\"| node3",
+            "    node1 -->|\"<\"| node2",
+            "    classDef branch fill:#fce283,stroke:#333",
+            "    classDef leaf fill:#95fc83,stroke:#333",
+            "    class node1 branch",
+            "    class node3,node2 leaf",
+        ];
+        assert_eq!(tree.mermaid_dump("<", ">\""), expected.join("\n") + "\n");
+    }
+}
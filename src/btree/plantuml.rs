@@ -0,0 +1,47 @@
+use super::Tree;
+use std::fmt::Display;
+
+// PlantUML has no escape for quotes inside quoted names
+fn text(label: &str) -> String {
+    label.replace('"', "'").replace('\n', "\\n")
+}
+
+pub(super) fn render<T: Display>(tree: &Tree<T>, left: &str, right: &str) -> String {
+    let mut lines = vec!["@startuml".to_string()];
+    let mut edges = Vec::new();
+    for item in tree.pre_order_iter() {
+        let name = format!("node{}", item.id);
+        let (shape, color) = if item.leaf { ("card", "#95fc83") } else { ("rectangle", "#fce283") };
+        lines.push(format!("{} \"{}\" as {} {}", shape, text(&item.value.to_string()), name, color));
+        if item.id > 1 {
+            let edgelabel = if item.id % 2 == 0 { left } else { right };
+            edges.push(format!("node{} --> {} : {}", item.id >> 1, name, text(edgelabel)));
+        }
+    }
+    lines.extend(edges);
+    lines.push("@enduml".into());
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linked_rectangles() {
+        let mut tree: Tree<i64> = Tree::new();
+        for num in [2, 1, 3] {
+            tree.insert(num);
+        }
+        let expected = [
+            "@startuml",
+            "rectangle \"2\" as node1 #fce283",
+            "card \"3\" as node3 #95fc83",
+            "card \"1\" as node2 #95fc83",
+            "node1 --> node3 : >",
+            "node1 --> node2 : <",
+            "@enduml",
+        ];
+        assert_eq!(tree.plantuml_dump("<", ">"), expected.join("\n") + "\n");
+    }
+}
@@ -0,0 +1,235 @@
+use super::Node;
+use crate::plot::escape;
+use std::fmt::Write;
+
+// text metrics of the monospace font used in the boxes
+const FONT_SIZE: f64 = 12.0;
+const CHAR_WIDTH: f64 = 7.2;
+const LINE_HEIGHT: f64 = 15.0;
+const PADDING: f64 = 8.0;
+// horizontal space between neighbouring subtrees and vertical space between levels
+const SIBLING_GAP: f64 = 16.0;
+const LEVEL_GAP: f64 = 48.0;
+const MARGIN: f64 = 20.0;
+
+const BRANCH_COLOR: &str = "#fce283";
+const LEAF_COLOR: &str = "#95fc83";
+
+// a subtree laid out around the center of its root box
+struct Placed {
+    lines: Vec<String>,
+    width: f64,
+    height: f64,
+    leaf: bool,
+    // horizontal offset of each child from this node, and whether it is the left one
+    children: Vec<(f64, bool, Placed)>,
+    // leftmost and rightmost extent of the subtree at each depth, relative to this node
+    contour: Vec<(f64, f64)>,
+}
+
+// Reingold-Tilford: children are pushed apart until their contours no longer
+// overlap at any depth, then the parent is centered above them; a missing
+// sibling counts as a point so that single children still lean to their side
+fn place<T>(node: &Node<T>, label: &dyn Fn(&T) -> Vec<String>) -> Placed {
+    let mut lines = label(&node.value);
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let width = longest as f64 * CHAR_WIDTH + 2.0 * PADDING;
+    let height = lines.len() as f64 * LINE_HEIGHT + PADDING;
+
+    let left = node.left.as_deref().map(|child| place(child, label));
+    let right = node.right.as_deref().map(|child| place(child, label));
+    let point = vec![(0.0, 0.0)];
+    let left_contour = left.as_ref().map_or(&point, |p| &p.contour);
+    let right_contour = right.as_ref().map_or(&point, |p| &p.contour);
+    let separation = left_contour
+        .iter()
+        .zip(right_contour)
+        .map(|((_, l_right), (r_left, _))| l_right - r_left + SIBLING_GAP)
+        .fold(0.0, f64::max);
+
+    let mut contour = vec![(-width / 2.0, width / 2.0)];
+    let mut children = Vec::new();
+    for (child, offset, is_left) in [(left, -separation / 2.0, true), (right, separation / 2.0, false)] {
+        if let Some(child) = child {
+            for (depth, (low, high)) in child.contour.iter().enumerate() {
+                match contour.get_mut(depth + 1) {
+                    Some(extent) => *extent = (extent.0.min(low + offset), extent.1.max(high + offset)),
+                    None => contour.push((low + offset, high + offset)),
+                }
+            }
+            children.push((offset, is_left, child));
+        }
+    }
+    Placed {
+        lines,
+        width,
+        height,
+        leaf: children.is_empty(),
+        children,
+        contour,
+    }
+}
+
+fn level_heights(placed: &Placed, depth: usize, heights: &mut Vec<f64>) {
+    if heights.len() <= depth {
+        heights.push(0.0);
+    }
+    heights[depth] = heights[depth].max(placed.height);
+    for (_, _, child) in &placed.children {
+        level_heights(child, depth + 1, heights);
+    }
+}
+
+struct Canvas<'a> {
+    // top of each level
+    tops: Vec<f64>,
+    left: &'a str,
+    right: &'a str,
+    boxes: String,
+    edges: String,
+}
+
+impl<'a> Canvas<'a> {
+    fn draw(&mut self, placed: &Placed, x: f64, depth: usize) {
+        let top = self.tops[depth];
+        let _ = writeln!(
+            self.boxes,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" rx="{}" fill="{}" stroke="black"/>"#,
+            x - placed.width / 2.0,
+            top,
+            placed.width,
+            placed.height,
+            if placed.leaf { 6 } else { 0 },
+            if placed.leaf { LEAF_COLOR } else { BRANCH_COLOR }
+        );
+        for (i, line) in placed.lines.iter().enumerate() {
+            let _ = writeln!(
+                self.boxes,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                x,
+                top + PADDING / 2.0 + (i as f64 + 0.8) * LINE_HEIGHT,
+                escape(line)
+            );
+        }
+        for (offset, is_left, child) in &placed.children {
+            let (x1, y1) = (x, top + placed.height);
+            let (x2, y2) = (x + offset, self.tops[depth + 1]);
+            let _ = writeln!(
+                self.edges,
+                r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="black"/>"#,
+                x1, y1, x2, y2
+            );
+            let label = if *is_left { self.left } else { self.right };
+            let _ = writeln!(
+                self.edges,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="{}">{}</text>"#,
+                (x1 + x2) / 2.0 + if *is_left { -4.0 } else { 4.0 },
+                (y1 + y2) / 2.0,
+                if *is_left { "end" } else { "start" },
+                escape(label)
+            );
+            self.draw(child, x + offset, depth + 1);
+        }
+    }
+}
+
+// lays out the tree top down and renders it as a standalone svg document
+// `label` gives the lines of text in the box of a node
+pub(super) fn render<T>(root: Option<&Node<T>>, left: &str, right: &str, label: &dyn Fn(&T) -> Vec<String>) -> String {
+    let placed = match root {
+        Some(root) => place(root, label),
+        None => return r#"<svg xmlns="http://www.w3.org/2000/svg" width="0" height="0"/>"#.to_string() + "\n",
+    };
+    let mut heights = Vec::new();
+    level_heights(&placed, 0, &mut heights);
+    let mut tops = vec![MARGIN];
+    for height in &heights {
+        tops.push(tops.last().unwrap() + height + LEVEL_GAP);
+    }
+    let low = placed.contour.iter().map(|c| c.0).fold(0.0, f64::min);
+    let high = placed.contour.iter().map(|c| c.1).fold(0.0, f64::max);
+    let width = high - low + 2.0 * MARGIN;
+    let height = tops[heights.len()] - LEVEL_GAP + MARGIN;
+
+    let mut canvas = Canvas {
+        tops,
+        left,
+        right,
+        boxes: String::new(),
+        edges: String::new(),
+    };
+    canvas.draw(&placed, MARGIN - low, 0);
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="0 0 {:.0} {:.0}" "#,
+            r#"font-family="monospace" font-size="{}">"#,
+            "\n{}{}</svg>\n"
+        ),
+        width, height, width, height, FONT_SIZE, canvas.edges, canvas.boxes
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Tree;
+    use crate::test::iris;
+    use crate::{DTreeBuilder, Decision};
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+    use std::collections::HashSet;
+
+    #[test]
+    fn boxes_do_not_overlap() {
+        let mut tree: Tree<i64> = Tree::new();
+        for num in [6, 1, 0, 2, 5, 4, 9, 8, 3, 7] {
+            tree.insert(num);
+        }
+        let svg = tree.svg_dump("<", ">");
+        let mut reader = Reader::from_str(&svg);
+        let mut boxes: Vec<(f64, f64, f64)> = Vec::new();
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Empty(e) if e.name().as_ref() == b"rect" => {
+                    let attribute = |name: &[u8]| -> f64 {
+                        let value = e.try_get_attribute(name).unwrap().unwrap().value;
+                        std::str::from_utf8(&value).unwrap().parse().unwrap()
+                    };
+                    boxes.push((attribute(b"x"), attribute(b"y"), attribute(b"width")));
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        assert_eq!(boxes.len(), 10);
+        for (i, a) in boxes.iter().enumerate() {
+            assert!(a.0 >= 0.0);
+            for b in &boxes[i + 1..] {
+                if a.1 == b.1 {
+                    assert!(a.0 + a.2 <= b.0 || b.0 + b.2 <= a.0, "{:?} overlaps {:?}", a, b);
+                }
+            }
+        }
+        assert_eq!(svg.matches("<line").count(), 9);
+        assert!(svg.contains("&lt;"));
+        assert!(Tree::<i64>::new().svg_dump("<", ">").starts_with("<svg"));
+    }
+
+    #[test]
+    fn label_lines() {
+        // only `Decision` labels use the DOT escape, other values keep a literal `\n`
+        let mut text: Tree<String> = Tree::new();
+        text.insert(r"a\nb".to_string());
+        assert!(text.svg_dump("<", ">").contains(r">a\nb</text>"));
+
+        let tree = DTreeBuilder::new(HashSet::from(["petal_length"]), "variety")
+            .set_max_level(2)
+            .build(&iris())
+            .unwrap();
+        let svg = tree.svg_dump_with("yes", "no", Decision::label_lines);
+        assert!(!svg.contains(r"\n"));
+        assert!(svg.contains(">gini: "));
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(feature = "serde")]
+use crate::model::Hyperparameters;
+#[cfg(feature = "config")]
+use polars::prelude::*;
+#[cfg(feature = "config")]
+use std::path::Path;
+
+/// The only split criterion implemented by `DTreeBuilder`
+pub const GINI: &str = "gini";
+
+/// Everything needed to repeat a training run: data, schema and stopping rules
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct TrainingConfig {
+    // path of the training data, relative to the working directory
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub data: Option<String>,
+    pub target: String,
+    // all numeric columns but the target and the weight when empty
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub features: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default = "default_criterion"))]
+    pub criterion: String,
+    #[cfg_attr(feature = "serde", serde(default = "default_max_level"))]
+    pub max_level: usize,
+    #[cfg_attr(feature = "serde", serde(default = "default_min_size"))]
+    pub min_size: usize,
+    #[cfg_attr(feature = "serde", serde(default = "default_reuse_features"))]
+    pub reuse_features: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub weight: Option<String>,
+}
+
+// defaults match `DTreeBuilder::new`
+#[cfg(feature = "serde")]
+fn default_criterion() -> String {
+    GINI.to_string()
+}
+
+#[cfg(feature = "serde")]
+fn default_max_level() -> usize {
+    Hyperparameters::default().max_level
+}
+
+#[cfg(feature = "serde")]
+fn default_min_size() -> usize {
+    Hyperparameters::default().min_size
+}
+
+#[cfg(feature = "serde")]
+fn default_reuse_features() -> bool {
+    Hyperparameters::default().reuse_features
+}
+
+#[cfg(feature = "config")]
+impl TrainingConfig {
+    pub fn from_toml(text: &str) -> PolarsResult<TrainingConfig> {
+        toml::from_str(text).map_err(|e| polars_err!(ComputeError: "invalid TOML configuration: {}", e))
+    }
+
+    pub fn from_yaml(text: &str) -> PolarsResult<TrainingConfig> {
+        serde_yaml_ng::from_str(text).map_err(|e| polars_err!(ComputeError: "invalid YAML configuration: {}", e))
+    }
+
+    pub fn to_toml(&self) -> PolarsResult<String> {
+        toml::to_string(self).map_err(|e| polars_err!(ComputeError: "cannot write TOML configuration: {}", e))
+    }
+
+    pub fn to_yaml(&self) -> PolarsResult<String> {
+        serde_yaml_ng::to_string(self).map_err(|e| polars_err!(ComputeError: "cannot write YAML configuration: {}", e))
+    }
+
+    /// Reads a `.toml`, `.yaml` or `.yml` file
+    pub fn load<P: AsRef<Path>>(path: P) -> PolarsResult<TrainingConfig> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| polars_err!(ComputeError: "cannot read {}: {}", path.display(), e))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => TrainingConfig::from_toml(&text),
+            Some("yaml") | Some("yml") => TrainingConfig::from_yaml(&text),
+            _ => polars_bail!(ComputeError: "{} is neither a TOML nor a YAML file", path.display()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "config"))]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+
+    #[test]
+    fn config_round_trip() {
+        let toml = r#"
+            data = "src/iris.csv"
+            target = "variety"
+            features = ["petal_width", "petal_length"]
+            max_level = 2
+        "#;
+        let config = TrainingConfig::from_toml(toml).unwrap();
+        assert_eq!(config.criterion, GINI);
+        assert_eq!(config.min_size, 1);
+        assert!(config.reuse_features);
+
+        let yaml = "target: variety\nfeatures: [petal_width, petal_length]\nmax_level: 2\ndata: src/iris.csv\n";
+        assert_eq!(TrainingConfig::from_yaml(yaml).unwrap(), config);
+        assert_eq!(TrainingConfig::from_toml(&config.to_toml().unwrap()).unwrap(), config);
+        assert_eq!(TrainingConfig::from_yaml(&config.to_yaml().unwrap()).unwrap(), config);
+
+        let model = DTreeBuilder::from_config(&config).unwrap().fit(&iris()).unwrap();
+        let mut resolved = config.clone();
+        resolved.features.sort();
+        assert_eq!(model.config, Some(resolved));
+        assert_eq!(model.tree.pre_order_iter().map(|item| item.level).max(), Some(3));
+
+        let entropy = TrainingConfig {
+            criterion: "entropy".into(),
+            ..config
+        };
+        assert!(DTreeBuilder::from_config(&entropy).is_err());
+        assert!(TrainingConfig::from_toml("target = \"variety\"\nmax_depth = 2").is_err());
+
+        // omitted stopping rules are those of a new builder
+        let minimal = TrainingConfig::from_toml("target = \"variety\"").unwrap();
+        let builder = DTreeBuilder::from_config(&minimal).unwrap();
+        assert_eq!(builder.parameters(), DTreeBuilder::new(Vec::<String>::new(), "variety").parameters());
+    }
+}
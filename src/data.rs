@@ -0,0 +1,201 @@
+use polars::prelude::*;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// File formats understood by `DataLoader`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Parquet,
+    // Arrow IPC, also known as Feather v2
+    Ipc,
+    // a single JSON array of row objects
+    Json,
+    // one JSON object per line
+    NdJson,
+}
+
+impl Format {
+    /// Guesses the format from the file extension
+    pub fn from_path(path: &Path) -> PolarsResult<Format> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "csv" | "tsv" | "txt" => Ok(Format::Csv),
+            "parquet" | "pq" => Ok(Format::Parquet),
+            "arrow" | "ipc" | "feather" => Ok(Format::Ipc),
+            "json" => Ok(Format::Json),
+            "ndjson" | "jsonl" => Ok(Format::NdJson),
+            _ => polars_bail!(
+                ComputeError: "cannot tell the format of {} from its extension, please specify it",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Reads a dataframe choosing the polars reader by format
+pub struct DataLoader {
+    path: PathBuf,
+    format: Option<Format>,
+    separator: u8,
+    has_header: bool,
+    null_values: Vec<String>,
+}
+
+impl DataLoader {
+    pub fn new<P: AsRef<Path>>(path: P) -> DataLoader {
+        DataLoader {
+            path: path.as_ref().to_path_buf(),
+            format: None,
+            separator: b',',
+            has_header: true,
+            null_values: Vec::new(),
+        }
+    }
+
+    // overrides the format guessed from the extension
+    pub fn set_format(mut self, format: Format) -> DataLoader {
+        self.format = Some(format);
+        self
+    }
+
+    // CSV only
+    pub fn set_separator(mut self, separator: u8) -> DataLoader {
+        self.separator = separator;
+        self
+    }
+
+    // CSV only
+    pub fn set_has_header(mut self, has_header: bool) -> DataLoader {
+        self.has_header = has_header;
+        self
+    }
+
+    // CSV only, strings read as missing values in every column
+    pub fn set_null_values(mut self, null_values: Vec<String>) -> DataLoader {
+        self.null_values = null_values;
+        self
+    }
+
+    pub fn format(&self) -> PolarsResult<Format> {
+        match self.format {
+            Some(format) => Ok(format),
+            None => Format::from_path(&self.path),
+        }
+    }
+
+    pub fn load(&self) -> PolarsResult<DataFrame> {
+        let format = self.format()?;
+        let file = File::open(&self.path)
+            .map_err(|e| polars_err!(ComputeError: "cannot read {}: {}", self.path.display(), e))?;
+        match format {
+            Format::Csv => {
+                let null_values = if self.null_values.is_empty() {
+                    None
+                } else {
+                    Some(NullValues::AllColumns(self.null_values.clone()))
+                };
+                CsvReader::new(file)
+                    .has_header(self.has_header)
+                    .with_separator(self.separator)
+                    .with_null_values(null_values)
+                    .finish()
+            }
+            Format::Parquet => ParquetReader::new(file).finish(),
+            Format::Ipc => IpcReader::new(file).finish(),
+            Format::Json => JsonReader::new(file).with_json_format(JsonFormat::Json).finish(),
+            Format::NdJson => JsonReader::new(file).with_json_format(JsonFormat::JsonLines).finish(),
+        }
+    }
+}
+
+/// Names of the numeric columns, skipping the excluded ones
+pub fn numeric_columns(data: &DataFrame, excluded: &[&str]) -> Vec<String> {
+    data.get_columns()
+        .iter()
+        .filter(|s| s.dtype().is_numeric() && !excluded.contains(&s.name()))
+        .map(|s| s.name().to_string())
+        .collect()
+}
+
+/// Casts the target to a categorical column and checks that every feature
+/// exists and is numeric
+pub fn prepare_training_data(data: &mut DataFrame, target: &str, features: &[&str]) -> PolarsResult<()> {
+    polars_ensure!(
+        data.get_column_names().contains(&target),
+        ColumnNotFound: "target column `{}` not found", target
+    );
+    for feature in features {
+        let column = data
+            .column(feature)
+            .map_err(|_| polars_err!(ColumnNotFound: "feature column `{}` not found", feature))?;
+        polars_ensure!(
+            column.dtype().is_numeric(),
+            SchemaMismatch: "feature column `{}` has type {}, expected a number", feature, column.dtype()
+        );
+    }
+    if !matches!(data.column(target)?.dtype(), DataType::Categorical(_, _)) {
+        data.try_apply(target, |s| {
+            s.cast(&DataType::String)?
+                .cast(&DataType::Categorical(None, CategoricalOrdering::Lexical))
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+
+    #[test]
+    fn formats_round_trip() {
+        let mut data = iris();
+        data.try_apply("variety", |s| s.cast(&DataType::String)).unwrap();
+        let directory = std::env::temp_dir();
+        let prefix = format!("decision-data-{}", std::process::id());
+        for (extension, format) in [
+            ("parquet", Format::Parquet),
+            ("arrow", Format::Ipc),
+            ("json", Format::Json),
+            ("jsonl", Format::NdJson),
+            ("tsv", Format::Csv),
+        ] {
+            let path = directory.join(format!("{}.{}", prefix, extension));
+            let mut file = File::create(&path).unwrap();
+            match format {
+                Format::Parquet => {
+                    ParquetWriter::new(&mut file).finish(&mut data).unwrap();
+                }
+                Format::Ipc => IpcWriter::new(&mut file).finish(&mut data).unwrap(),
+                Format::Json => JsonWriter::new(&mut file)
+                    .with_json_format(JsonFormat::Json)
+                    .finish(&mut data)
+                    .unwrap(),
+                Format::NdJson => JsonWriter::new(&mut file)
+                    .with_json_format(JsonFormat::JsonLines)
+                    .finish(&mut data)
+                    .unwrap(),
+                Format::Csv => CsvWriter::new(&mut file).with_separator(b'\t').finish(&mut data).unwrap(),
+            }
+            let loader = DataLoader::new(&path).set_separator(b'\t');
+            assert_eq!(loader.format().unwrap(), format);
+            let mut loaded = loader.load().unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert!(loaded.equals(&data), "{:?} differs", format);
+
+            prepare_training_data(&mut loaded, "variety", &["petal_length"]).unwrap();
+            assert!(matches!(loaded.column("variety").unwrap().dtype(), DataType::Categorical(_, _)));
+        }
+
+        let mut loaded = data.clone();
+        let error = prepare_training_data(&mut loaded, "variety", &["variety"]).unwrap_err();
+        assert!(error.to_string().contains("expected a number"));
+        assert!(prepare_training_data(&mut loaded, "variety", &["petal"]).is_err());
+        assert!(Format::from_path(Path::new("iris.xlsx")).is_err());
+    }
+}
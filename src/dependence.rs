@@ -0,0 +1,254 @@
+use crate::btree::Tree;
+use crate::plot::{color, Plot};
+use crate::Decision;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+/// Evenly spaced values between the minimum and the maximum of a feature
+pub fn feature_grid(data: &DataFrame, feature: &str, points: usize) -> PolarsResult<Vec<f64>> {
+    let values = data.column(feature)?.cast(&DataType::Float64)?;
+    let values = values.f64()?;
+    let (low, high) = match (values.min(), values.max()) {
+        (Some(low), Some(high)) => (low, high),
+        _ => polars_bail!(ComputeError: "feature `{}` has no values", feature),
+    };
+    Ok(match points {
+        0 => Vec::new(),
+        1 => vec![(low + high) / 2.0],
+        _ => (0..points)
+            .map(|i| low + (high - low) * i as f64 / (points - 1) as f64)
+            .collect(),
+    })
+}
+
+// every combination of the grid values, the first feature varying slowest
+fn combinations(grids: &[(&str, &[f64])]) -> Vec<Vec<f64>> {
+    grids.iter().fold(vec![Vec::new()], |partial, (_, grid)| {
+        partial
+            .iter()
+            .flat_map(|prefix| {
+                grid.iter().map(move |value| {
+                    let mut combination = prefix.clone();
+                    combination.push(*value);
+                    combination
+                })
+            })
+            .collect()
+    })
+}
+
+// class probabilities of each row with the grid features forced to a combination
+fn conditional_probabilities(
+    tree: &Tree<Decision>,
+    columns: &HashMap<String, Vec<Option<f64>>>,
+    rows: usize,
+    grids: &[(&str, &[f64])],
+    combination: &[f64],
+    classes: &[String],
+) -> Vec<Vec<f64>> {
+    (0..rows)
+        .map(|row| {
+            let leaf = tree.leaf(|feature| {
+                match grids.iter().position(|(name, _)| *name == feature) {
+                    Some(index) => Some(combination[index]),
+                    None => columns.get(feature).and_then(|c| c[row]),
+                }
+            });
+            classes
+                .iter()
+                .map(|class| leaf.map_or(0.0, |decision| decision.probability(class)))
+                .collect()
+        })
+        .collect()
+}
+
+fn dependence_frame(
+    grids: &[(&str, &[f64])],
+    rows: Option<Vec<u32>>,
+    points: Vec<Vec<f64>>,
+    classes: &[String],
+    probabilities: Vec<Vec<f64>>,
+) -> PolarsResult<DataFrame> {
+    let mut series = Vec::new();
+    if let Some(rows) = rows {
+        series.push(Series::new("row", rows));
+    }
+    for (i, (feature, _)) in grids.iter().enumerate() {
+        let values: Vec<f64> = points.iter().map(|point| point[i]).collect();
+        series.push(Series::new(feature, values));
+    }
+    for (c, class) in classes.iter().enumerate() {
+        let values: Vec<f64> = probabilities.iter().map(|p| p[c]).collect();
+        series.push(Series::new(class, values));
+    }
+    DataFrame::new(series)
+}
+
+/// Individual conditional expectation curves: the class probabilities of
+/// each row when the chosen features (one or two) take the grid values
+pub fn individual_conditional_expectation(
+    tree: &Tree<Decision>,
+    data: &DataFrame,
+    grids: &[(&str, &[f64])],
+) -> PolarsResult<DataFrame> {
+    polars_ensure!(!grids.is_empty(), ComputeError: "at least one feature grid is required");
+    let classes = tree.classes();
+    let columns = tree.feature_columns(data)?;
+    let mut rows = Vec::new();
+    let mut points = Vec::new();
+    let mut probabilities = Vec::new();
+    for combination in combinations(grids) {
+        let conditional = conditional_probabilities(tree, &columns, data.height(), grids, &combination, &classes);
+        for (row, probability) in conditional.into_iter().enumerate() {
+            rows.push(row as u32);
+            points.push(combination.clone());
+            probabilities.push(probability);
+        }
+    }
+    dependence_frame(grids, Some(rows), points, &classes, probabilities)
+}
+
+/// Partial dependence: the class probabilities averaged over all rows when
+/// the chosen features (one or two) take the grid values
+pub fn partial_dependence(
+    tree: &Tree<Decision>,
+    data: &DataFrame,
+    grids: &[(&str, &[f64])],
+) -> PolarsResult<DataFrame> {
+    polars_ensure!(!grids.is_empty(), ComputeError: "at least one feature grid is required");
+    let classes = tree.classes();
+    let columns = tree.feature_columns(data)?;
+    let total = data.height().max(1) as f64;
+    let mut points = Vec::new();
+    let mut probabilities = Vec::new();
+    for combination in combinations(grids) {
+        let conditional = conditional_probabilities(tree, &columns, data.height(), grids, &combination, &classes);
+        let mean: Vec<f64> = (0..classes.len())
+            .map(|c| conditional.iter().map(|p| p[c]).sum::<f64>() / total)
+            .collect();
+        points.push(combination);
+        probabilities.push(mean);
+    }
+    dependence_frame(grids, None, points, &classes, probabilities)
+}
+
+fn float_column(data: &DataFrame, name: &str) -> PolarsResult<Vec<f64>> {
+    Ok(data.column(name)?.f64()?.into_iter().map(|v| v.unwrap_or(f64::NAN)).collect())
+}
+
+/// Renders the partial dependence of a class on one feature as an svg line
+/// chart, with the individual curves in the background when given
+pub fn dependence_plot(
+    partial: &DataFrame,
+    individual: Option<&DataFrame>,
+    feature: &str,
+    class: &str,
+) -> PolarsResult<String> {
+    let x = float_column(partial, feature)?;
+    let y = float_column(partial, class)?;
+    polars_ensure!(!x.is_empty(), ComputeError: "nothing to plot for class `{}`", class);
+    // min and max skip NaN, so a column without numbers keeps the infinite start values
+    let low = x.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = x.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    polars_ensure!(
+        low.is_finite() && high.is_finite(),
+        ComputeError: "feature `{}` has no finite values to plot", feature
+    );
+    let mut plot = Plot::new((low, high), (0.0, 1.0));
+    if let Some(individual) = individual {
+        let rows: Vec<u32> = individual.column("row")?.u32()?.into_iter().flatten().collect();
+        let ix = float_column(individual, feature)?;
+        let iy = float_column(individual, class)?;
+        let mut curves: Vec<Vec<(f64, f64)>> = Vec::new();
+        for ((row, x), y) in rows.into_iter().zip(ix).zip(iy) {
+            let row = row as usize;
+            if curves.len() <= row {
+                curves.resize(row + 1, Vec::new());
+            }
+            curves[row].push((x, y));
+        }
+        for curve in curves.iter_mut() {
+            curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+            plot.polyline(curve, "#999999", 1.0, 0.3);
+        }
+    }
+    let mut points: Vec<(f64, f64)> = x.into_iter().zip(y).collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    plot.polyline(&points, color(0), 3.0, 1.0);
+    plot.axes(feature, &format!("P({})", class));
+    Ok(plot.to_string())
+}
+
+/// Renders the partial dependence of a class on two features as an svg
+/// heatmap, darker cells meaning higher probability
+pub fn dependence_heatmap(partial: &DataFrame, features: (&str, &str), class: &str) -> PolarsResult<String> {
+    let x = float_column(partial, features.0)?;
+    let y = float_column(partial, features.1)?;
+    let p = float_column(partial, class)?;
+    polars_ensure!(!p.is_empty(), ComputeError: "nothing to plot for class `{}`", class);
+    let mut xs = x.clone();
+    let mut ys = y.clone();
+    for values in [&mut xs, &mut ys] {
+        values.sort_by(|a, b| a.total_cmp(b));
+        values.dedup();
+    }
+    // each cell spans half way to the neighbouring grid values
+    let bounds = |values: &[f64], v: f64| -> (f64, f64) {
+        let i = values.iter().position(|x| *x == v).unwrap_or(0);
+        let low = if i == 0 { v } else { (values[i - 1] + v) / 2.0 };
+        let high = if i + 1 == values.len() { v } else { (values[i + 1] + v) / 2.0 };
+        (low, high)
+    };
+    let mut plot = Plot::new((xs[0], xs[xs.len() - 1]), (ys[0], ys[ys.len() - 1]));
+    for ((x, y), p) in x.into_iter().zip(y).zip(p) {
+        let (x0, x1) = bounds(&xs, x);
+        let (y0, y1) = bounds(&ys, y);
+        plot.rect((x0, y0), (x1, y1), color(0), p.clamp(0.0, 1.0));
+    }
+    plot.axes(features.0, features.1);
+    plot.legend(&[(color(0), &format!("P({})", class))]);
+    Ok(plot.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use std::collections::HashSet;
+
+    #[test]
+    fn partial_dependence_averages_individual_curves() {
+        let data = iris();
+        let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
+        let tree = DTreeBuilder::new(features, "variety")
+            .set_max_level(3)
+            .build(&data)
+            .unwrap();
+        let grid = feature_grid(&data, "petal_width", 5).unwrap();
+        assert_eq!(grid.len(), 5);
+        let grids = [("petal_width", grid.as_slice())];
+        let individual = individual_conditional_expectation(&tree, &data, &grids).unwrap();
+        let partial = partial_dependence(&tree, &data, &grids).unwrap();
+        assert_eq!(individual.height(), 5 * data.height());
+        assert_eq!(partial.height(), 5);
+
+        let averaged = individual
+            .lazy()
+            .group_by([col("petal_width")])
+            .agg([col("Setosa").mean()])
+            .sort(["petal_width"], SortMultipleOptions::default())
+            .collect()
+            .unwrap();
+        let expected = averaged.column("Setosa").unwrap().f64().unwrap();
+        let actual = partial.column("Setosa").unwrap().f64().unwrap();
+        for (e, a) in expected.into_iter().zip(actual) {
+            assert!((e.unwrap() - a.unwrap()).abs() < 1e-9);
+        }
+        let svg = dependence_plot(&partial, None, "petal_width", "Setosa").unwrap();
+        assert!(svg.starts_with("<svg") && svg.contains("<polyline"));
+        assert!(dependence_plot(&partial.head(Some(0)), None, "petal_width", "Setosa").is_err());
+        let missing = df!("petal_width" => [f64::NAN, f64::NAN], "Setosa" => [0.5, 0.5]).unwrap();
+        assert!(dependence_plot(&missing, None, "petal_width", "Setosa").is_err());
+    }
+}
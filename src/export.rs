@@ -0,0 +1,9 @@
+// exporters of trained trees into formats understood by other tools
+pub mod codegen;
+pub mod dot;
+#[cfg(feature = "serde")]
+pub mod html;
+pub mod onnx;
+pub mod pmml;
+pub mod sql;
+pub mod text;
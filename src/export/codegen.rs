@@ -0,0 +1,322 @@
+use crate::btree::Node;
+use crate::model::Model;
+use crate::{Decision, Rule};
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Target languages of the generated source code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    C,
+}
+
+// string literal in the syntax of the target language
+fn literal(text: &str, language: Language) -> String {
+    match language {
+        Language::Rust => format!("{:?}", text),
+        Language::C => {
+            let mut quoted = String::from("\"");
+            for c in text.chars() {
+                match c {
+                    '\\' => quoted.push_str("\\\\"),
+                    '"' => quoted.push_str("\\\""),
+                    '\n' => quoted.push_str("\\n"),
+                    '\t' => quoted.push_str("\\t"),
+                    // octal escapes never swallow the following characters
+                    c if c.is_ascii_control() => {
+                        let _ = write!(quoted, "\\{:03o}", c as u32);
+                    }
+                    c => quoted.push(c),
+                }
+            }
+            quoted.push('"');
+            quoted
+        }
+    }
+}
+
+struct Generator<'a> {
+    language: Language,
+    features: HashMap<&'a str, usize>,
+    classes: HashMap<&'a str, usize>,
+    source: String,
+}
+
+// the rule and children of a node that splits
+fn split(node: &Node<Decision>) -> Option<(&Rule, &Node<Decision>, &Node<Decision>)> {
+    match (node.value.rule(), &node.left, &node.right) {
+        (Some(rule), Some(left), Some(right)) => Some((rule, left, right)),
+        _ => None,
+    }
+}
+
+impl<'a> Generator<'a> {
+    // nested if/else mirroring the tree, missing values (NaN) take the else branch
+    fn write_node(&mut self, node: &Node<Decision>, depth: usize) -> PolarsResult<()> {
+        let indent = "    ".repeat(depth);
+        match split(node) {
+            Some((rule, left, right)) => {
+                self.source.push_str(&indent);
+                self.write_split(rule, left, right, depth)
+            }
+            None => {
+                let class = *self.classes.get(node.value.prediction()).ok_or_else(
+                    || polars_err!(ComputeError: "unknown class `{}`", node.value.prediction()),
+                )?;
+                // the Rust function evaluates to the class of the leaf, keeping clippy quiet
+                let value = match self.language {
+                    Language::Rust => class.to_string(),
+                    Language::C => format!("return {};", class),
+                };
+                let _ = writeln!(
+                    self.source,
+                    "{}{} // {}",
+                    indent,
+                    value,
+                    literal(node.value.prediction(), self.language)
+                );
+                Ok(())
+            }
+        }
+    }
+
+    // an `if` whose else branch continues as `else if` when it splits again
+    fn write_split(&mut self, rule: &Rule, left: &Node<Decision>, right: &Node<Decision>, depth: usize) -> PolarsResult<()> {
+        let indent = "    ".repeat(depth);
+        let feature = *self.features.get(rule.dimension()).ok_or_else(
+            || polars_err!(ColumnNotFound: "the tree splits on unknown feature `{}`", rule.dimension()),
+        )?;
+        let condition = format!("features[{}] > {:?}", feature, rule.cutoff());
+        let condition = match self.language {
+            Language::Rust => condition,
+            Language::C => format!("({})", condition),
+        };
+        let _ = writeln!(
+            self.source,
+            "if {} {{ // {}",
+            condition,
+            literal(rule.dimension(), self.language)
+        );
+        self.write_node(left, depth + 1)?;
+        match split(right) {
+            Some((rule, left, right)) => {
+                let _ = write!(self.source, "{}}} else ", indent);
+                self.write_split(rule, left, right, depth)
+            }
+            None => {
+                let _ = writeln!(self.source, "{}}} else {{", indent);
+                self.write_node(right, depth + 1)?;
+                let _ = writeln!(self.source, "{}}}", indent);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Model {
+    /// Generates a dependency free `predict` function returning the index
+    /// of the predicted class in `CLASSES`; `features` follows the order
+    /// of the `FEATURES` table
+    pub fn to_source(&self, language: Language) -> PolarsResult<String> {
+        let root = self
+            .tree
+            .root()
+            .ok_or_else(|| polars_err!(ComputeError: "cannot generate code for an empty tree"))?;
+        let mut generator = Generator {
+            language,
+            features: self.features.iter().enumerate().map(|(i, f)| (f.as_str(), i)).collect(),
+            classes: self.classes.iter().enumerate().map(|(i, c)| (c.as_str(), i)).collect(),
+            source: String::new(),
+        };
+        let features: Vec<String> = self.features.iter().map(|f| literal(f, language)).collect();
+        let classes: Vec<String> = self.classes.iter().map(|c| literal(c, language)).collect();
+        let header = format!(
+            "generated by {} {} from a tree predicting {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            literal(&self.target, language)
+        );
+        let source = &mut generator.source;
+        match language {
+            Language::Rust => {
+                let _ = writeln!(source, "// {}\n", header);
+                let _ = writeln!(source, "pub const FEATURES: [&str; {}] = [{}];", features.len(), features.join(", "));
+                let _ = writeln!(source, "pub const CLASSES: [&str; {}] = [{}];\n", classes.len(), classes.join(", "));
+                source.push_str("pub fn predict(features: &[f64]) -> usize {\n");
+            }
+            Language::C => {
+                let _ = writeln!(source, "// {}\n", header);
+                source.push_str("#include <stddef.h>\n\n");
+                let _ = writeln!(
+                    source,
+                    "const char *const FEATURES[{}] = {{{}}};",
+                    features.len(),
+                    features.join(", ")
+                );
+                let _ = writeln!(
+                    source,
+                    "const char *const CLASSES[{}] = {{{}}};\n",
+                    classes.len(),
+                    classes.join(", ")
+                );
+                source.push_str("size_t predict(const double *features) {\n");
+            }
+        }
+        generator.write_node(root, 1)?;
+        generator.source.push_str("}\n");
+        Ok(generator.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use std::collections::HashSet;
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn generated_source_mirrors_tree() {
+        let data = iris();
+        let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
+        let model = DTreeBuilder::new(features, "variety")
+            .set_max_level(3)
+            .fit(&data)
+            .unwrap();
+        let splits = model.tree.pre_order_iter().filter(|item| !item.leaf).count();
+        let leaves = model.tree.pre_order_iter().filter(|item| item.leaf).count();
+        for language in [Language::Rust, Language::C] {
+            let source = model.to_source(language).unwrap();
+            assert_eq!(source.matches("features[").count(), splits);
+            let returned = source.lines().filter(|l| l.trim_start().starts_with(|c: char| c.is_ascii_digit()));
+            let returned = returned.count() + source.matches("return ").count();
+            assert_eq!(returned, leaves);
+            assert!(source.contains(r#""Setosa", "Versicolor", "Virginica""#));
+        }
+        assert!(model.to_source(Language::C).unwrap().contains("size_t predict(const double *features)"));
+        assert_eq!(literal("a\"b\\\u{1}", Language::C), r#""a\"b\\\001""#);
+    }
+
+    // compiles the generated function with a main printing the class of
+    // every row, and returns the printed lines; `None` without a compiler
+    fn run_generated(model: &Model, language: Language, rows: &[Vec<f64>]) -> Option<Vec<String>> {
+        let (compiler, file) = match language {
+            Language::Rust => (std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into()), "main.rs"),
+            Language::C => (std::env::var("CC").unwrap_or_else(|_| "cc".into()), "main.c"),
+        };
+        if Command::new(&compiler).arg("--version").output().is_err() {
+            eprintln!("{} not found, skipping the {:?} code", compiler, language);
+            return None;
+        }
+        let values: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                let row: Vec<String> = row.iter().map(|v| format!("{:?}", v)).collect();
+                format!("{{{}}}", row.join(", "))
+            })
+            .collect();
+        let mut source = model.to_source(language).unwrap();
+        match language {
+            Language::Rust => {
+                let values = values.join(", ").replace('{', "[").replace('}', "]");
+                let _ = write!(
+                    source,
+                    "\nconst ROWS: [[f64; {}]; {}] = [{}];\n\nfn main() {{\n    for row in ROWS.iter() {{\n        println!(\"{{}}\", CLASSES[predict(row)]);\n    }}\n}}\n",
+                    model.features.len(),
+                    rows.len(),
+                    values
+                );
+            }
+            Language::C => {
+                source = format!("#include <stdio.h>\n{}", source);
+                let _ = write!(
+                    source,
+                    "\nstatic const double ROWS[{}][{}] = {{{}}};\n\nint main(void) {{\n    for (size_t i = 0; i < {}; i++) {{\n        printf(\"%s\\n\", CLASSES[predict(ROWS[i])]);\n    }}\n    return 0;\n}}\n",
+                    rows.len(),
+                    model.features.len(),
+                    values.join(", "),
+                    rows.len()
+                );
+            }
+        }
+        let directory = std::env::temp_dir().join(format!("decision-codegen-{}-{:?}", std::process::id(), language));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(file);
+        let binary = directory.join("predict");
+        fs::write(&path, source).unwrap();
+        let mut command = Command::new(&compiler);
+        match language {
+            Language::Rust => command.args(["--edition", "2021", "-o"]).arg(&binary).arg(&path),
+            Language::C => command.args(["-std=c99", "-Wall", "-Werror", "-o"]).arg(&binary).arg(&path),
+        };
+        let compiled = command.output().unwrap();
+        assert!(compiled.status.success(), "{}", String::from_utf8_lossy(&compiled.stderr));
+        let output = Command::new(&binary).output().unwrap();
+        assert!(output.status.success());
+        let _ = fs::remove_dir_all(&directory);
+        Some(String::from_utf8(output.stdout).unwrap().lines().map(|l| l.to_string()).collect())
+    }
+
+    // lints the generated Rust as a library with clippy's default lints
+    // denied; `None` without clippy
+    fn clippy_generated(model: &Model) -> Option<(bool, String)> {
+        let driver = std::env::var("CLIPPY_DRIVER").unwrap_or_else(|_| "clippy-driver".into());
+        if Command::new(&driver).arg("--version").output().is_err() {
+            eprintln!("{} not found, skipping the lint of the Rust code", driver);
+            return None;
+        }
+        let directory = std::env::temp_dir().join(format!("decision-clippy-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("predict.rs");
+        fs::write(&path, model.to_source(Language::Rust).unwrap()).unwrap();
+        let linted = Command::new(&driver)
+            .args(["--edition", "2021", "--crate-type", "lib", "-D", "warnings", "-D", "clippy::all", "--out-dir"])
+            .arg(&directory)
+            .arg(&path)
+            .output()
+            .unwrap();
+        let _ = fs::remove_dir_all(&directory);
+        Some((linted.status.success(), String::from_utf8_lossy(&linted.stderr).to_string()))
+    }
+
+    #[test]
+    fn compiled_code_predicts_like_the_model() {
+        let data = iris();
+        let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
+        let model = DTreeBuilder::new(features, "variety")
+            .set_max_level(5)
+            .fit(&data)
+            .unwrap();
+        let columns: Vec<Vec<f64>> = model
+            .features
+            .iter()
+            .map(|f| {
+                let values = data.column(f).unwrap().cast(&DataType::Float64).unwrap();
+                values.f64().unwrap().into_no_null_iter().collect()
+            })
+            .collect();
+        let rows: Vec<Vec<f64>> = (0..data.height())
+            .map(|i| columns.iter().map(|c| c[i]).collect())
+            .collect();
+        let expected: Vec<String> = model
+            .predict(&data)
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_iter()
+            .map(|p| p.unwrap().to_string())
+            .collect();
+        for language in [Language::Rust, Language::C] {
+            if let Some(predictions) = run_generated(&model, language, &rows) {
+                assert_eq!(predictions, expected, "{:?}", language);
+            }
+        }
+        if let Some((clean, warnings)) = clippy_generated(&model) {
+            assert!(clean, "{}", warnings);
+        }
+    }
+}
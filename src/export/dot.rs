@@ -0,0 +1,213 @@
+use crate::btree::dot::{AttributeValue, Dot, Highlight};
+use crate::btree::Tree;
+use crate::plot;
+use crate::Decision;
+use std::collections::HashSet;
+
+/// Rendering options of `Tree::to_dot`
+#[derive(Debug, Clone)]
+pub struct DotOptions {
+    // edge labels of the `>` and `<=` branches, as in `dot_dump`
+    pub left: String,
+    pub right: String,
+    // decimal places of thresholds, confidences and impurities
+    pub decimals: usize,
+    pub show_samples: bool,
+    // class counts of each node
+    pub show_distribution: bool,
+    // gini impurity of each node
+    pub show_impurity: bool,
+    // fill nodes with the color of their majority class, paler when mixed
+    pub class_colors: bool,
+    // draw edges thicker when more samples flow along them
+    pub edge_widths: bool,
+    // heap ids of the nodes to highlight, usually from `Tree::row_path`;
+    // when not empty everything else is greyed out
+    pub highlight: HashSet<usize>,
+    // attributes of the highlighted and the greyed out nodes and edges
+    pub highlight_style: Highlight,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        DotOptions {
+            left: "yes".into(),
+            right: "no".into(),
+            decimals: 2,
+            show_samples: true,
+            show_distribution: false,
+            show_impurity: false,
+            class_colors: true,
+            edge_widths: true,
+            highlight: HashSet::new(),
+            highlight_style: path_style(),
+        }
+    }
+}
+
+// the path in bold red, the rest in light grey
+fn path_style() -> Highlight {
+    let (red, grey) = ("#d62728", "#c0c0c0");
+    let mut style = Highlight::default();
+    style.node.set("style", "bold");
+    style.node.set("color", red);
+    style.node.set("penwidth", 3usize);
+    style.edge.set("penwidth", 3usize);
+    style.edge.set("color", red);
+    style.edge.set("style", "bold");
+    style.faded_node.set("fillcolor", "#f2f2f2");
+    style.faded_node.set("color", grey);
+    style.faded_node.set("fontcolor", grey);
+    style.faded_edge.set("color", grey);
+    style.faded_edge.set("fontcolor", grey);
+    style
+}
+
+// blends the class color with white, `purity` 0 gives white and 1 the full color
+fn shade(color: &str, purity: f64) -> String {
+    let purity = purity.clamp(0.0, 1.0);
+    let channel = |i: usize| {
+        let value = u8::from_str_radix(&color[i..i + 2], 16).unwrap_or(255) as f64;
+        (255.0 - (255.0 - value) * purity).round() as u8
+    };
+    format!("#{:02x}{:02x}{:02x}", channel(1), channel(3), channel(5))
+}
+
+// confidence rescaled so that a uniform mix of `classes` classes is 0
+fn purity(decision: &Decision, classes: usize) -> f64 {
+    if classes < 2 {
+        return 1.0;
+    }
+    let uniform = 1.0 / classes as f64;
+    (decision.confidence() - uniform) / (1.0 - uniform)
+}
+
+impl Tree<Decision> {
+    /// Renders the tree as a Graphviz digraph annotated with node statistics
+    pub fn to_dot(&self, options: &DotOptions) -> String {
+        let classes = self.classes();
+        let total = self.root().map_or(1, |root| root.value.samples().max(1)) as f64;
+        let decimals = options.decimals;
+        let mut graph = Dot::new();
+        for item in self.pre_order_iter() {
+            let decision = item.value;
+            let mut lines = vec![match decision.rule() {
+                Some(rule) => format!("{} > {:.*}", rule.dimension(), decimals, rule.cutoff()),
+                None => format!("{} {:.*}", decision.prediction(), decimals, decision.confidence()),
+            }];
+            if options.show_samples {
+                lines.push(format!("samples: {}", decision.samples()));
+            }
+            if options.show_distribution {
+                let counts: Vec<String> = decision
+                    .distribution()
+                    .iter()
+                    .map(|(class, count)| format!("{}: {}", class, count))
+                    .collect();
+                lines.push(counts.join(", "));
+            }
+            if options.show_impurity {
+                lines.push(format!("gini: {:.*}", decimals, decision.impurity()));
+            }
+            let fillcolor = if options.class_colors {
+                let index = classes.iter().position(|c| c == decision.prediction()).unwrap_or(0);
+                shade(plot::color(index), purity(decision, classes.len()))
+            } else if item.leaf {
+                "#95fc83".into()
+            } else {
+                "#fce283".into()
+            };
+            let name = format!("node{}", item.id);
+            graph
+                .node(&name)
+                .set("label", lines.join("\n"))
+                .set("shape", "box")
+                .set("style", if item.leaf { "rounded,filled" } else { "filled" })
+                .set("fillcolor", fillcolor);
+            if item.id > 1 {
+                let parent = format!("node{}", item.id >> 1);
+                let label = if item.id % 2 == 0 { &options.left } else { &options.right };
+                let edge = graph.edge(&parent, &name);
+                edge.set("label", label.as_str());
+                if options.edge_widths {
+                    let share = decision.samples() as f64 / total;
+                    let width = format!("{:.2}", 0.5 + 7.5 * share);
+                    edge.set("penwidth", AttributeValue::Id(width));
+                }
+            }
+            graph.append_rank(item.level - 1, &name);
+        }
+        if !options.highlight.is_empty() {
+            let names = options.highlight.iter().map(|id| format!("node{}", id)).collect();
+            graph.highlight(&names, &options.highlight_style);
+        }
+        graph.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+
+    #[test]
+    fn dot_statistics() {
+        let data = iris();
+        let tree = DTreeBuilder::new(HashSet::from(["petal_length"]), "variety")
+            .set_max_level(3)
+            .build(&data)
+            .unwrap();
+        let options = DotOptions {
+            show_distribution: true,
+            show_impurity: true,
+            ..DotOptions::default()
+        };
+        let dot = tree.to_dot(&options);
+        assert!(dot.contains(
+            r##"node3 [label="Setosa 1.00\nsamples: 50\nSetosa: 50\ngini: 0.00", shape="box", style="rounded,filled", fillcolor="#4e79a7"];"##
+        ));
+        // the root is an even mix of the three classes
+        assert!(dot.contains(r#"node1 [label="petal_length > 2.45\nsamples: 150"#));
+        assert!(dot.contains(r##"fillcolor="#ffffff"];"##));
+        assert!(dot.contains("node1 -> node3 [label=\"no\", penwidth=3.00]"));
+
+        let plain = tree.to_dot(&DotOptions {
+            class_colors: false,
+            edge_widths: false,
+            ..DotOptions::default()
+        });
+        assert!(plain.contains("node1 -> node3 [label=\"no\"]"));
+        assert_eq!(shade("#4e79a7", 0.5), "#a7bcd3");
+    }
+
+    #[test]
+    fn highlighted_path() {
+        let data = iris();
+        let tree = DTreeBuilder::new(HashSet::from(["petal_length"]), "variety")
+            .set_max_level(3)
+            .build(&data)
+            .unwrap();
+        // the first row is a setosa, left out by the first split
+        let path = tree.row_path(&data, 0).unwrap();
+        assert_eq!(path, vec![1, 3]);
+        let options = DotOptions {
+            highlight: path.iter().copied().collect(),
+            ..DotOptions::default()
+        };
+        let dot = tree.to_dot(&options);
+        assert!(dot.contains(r##"style="rounded,filled,bold", fillcolor="#4e79a7", color="#d62728", penwidth=3];"##));
+        assert!(dot.contains(r##"node1 -> node3 [label="no", penwidth=3.00, color="#d62728", style="bold"]"##));
+        assert!(dot.contains(r##"node1 -> node2 [label="yes", penwidth=5.50, color="#c0c0c0", fontcolor="#c0c0c0"]"##));
+        assert_eq!(dot.matches(r##"fillcolor="#f2f2f2""##).count(), tree.pre_order_iter().count() - 2);
+
+        let generic = tree.dot_dump_highlighted("yes", "no", &HashSet::from([1, 2]), &options.highlight_style);
+        assert!(generic.contains(r##"node1 -> node2 [label="yes", penwidth=3, color="#d62728", style="bold"]"##));
+        let mut blue = Highlight::default();
+        blue.edge.set("color", "blue");
+        let custom = tree.dot_dump_highlighted("yes", "no", &HashSet::from([1, 2]), &blue);
+        assert!(custom.contains(r#"node1 -> node2 [label="yes", color="blue"]"#));
+        assert!(!custom.contains("#d62728"));
+        assert!(tree.row_path(&data, 150).is_err());
+    }
+}
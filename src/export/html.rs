@@ -0,0 +1,178 @@
+use crate::btree::{Node, Tree};
+use crate::plot::escape;
+use crate::Decision;
+use polars::prelude::*;
+use serde_json::{json, Map, Value};
+
+// nested description of a subtree read by the viewer script
+fn node_json(node: &Node<Decision>, id: usize, edge: Option<&str>) -> Value {
+    let decision = &node.value;
+    let distribution: Map<String, Value> = decision
+        .distribution()
+        .iter()
+        .map(|(class, count)| (class.clone(), json!(count)))
+        .collect();
+    let mut children = Vec::new();
+    if let (Some(rule), Some(left), Some(right)) = (decision.rule(), &node.left, &node.right) {
+        children.push(node_json(
+            left,
+            id << 1,
+            Some(&format!("{} > {}", rule.dimension(), rule.cutoff())),
+        ));
+        children.push(node_json(
+            right,
+            (id << 1) + 1,
+            Some(&format!("{} <= {}", rule.dimension(), rule.cutoff())),
+        ));
+    }
+    json!({
+        "id": id,
+        "edge": edge,
+        "feature": decision.rule().map(|r| r.dimension()),
+        "prediction": decision.prediction(),
+        "confidence": decision.confidence(),
+        "samples": decision.samples(),
+        "weighted_samples": decision.weighted_samples(),
+        "impurity": decision.impurity(),
+        "depth": decision.depth(),
+        "distribution": distribution,
+        "children": children,
+    })
+}
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 1em 2em; }
+ul { list-style: none; padding-left: 1.4em; border-left: 1px dotted #aaa; }
+li > span { cursor: default; padding: 1px 4px; border-radius: 3px; }
+li.branch > span { cursor: pointer; background: #fce283; }
+li.leaf > span { background: #95fc83; }
+li.collapsed > ul { display: none; }
+li.branch > span::before { content: "\25BE  "; }
+li.branch.collapsed > span::before { content: "\25B8  "; }
+span.match { outline: 2px solid #d62728; }
+#details { position: fixed; top: 1em; right: 1em; width: 18em; background: #f7f7f7; border: 1px solid #ccc; padding: 0.5em 1em; white-space: pre; font-family: monospace; }
+"#;
+
+const SCRIPT: &str = r#"
+const tree = JSON.parse(document.getElementById("tree-data").textContent);
+const details = document.getElementById("details");
+const spans = [];
+
+function describe(node) {
+  const counts = Object.entries(node.distribution).map(([c, n]) => "  " + c + ": " + n).join("\n");
+  return "node " + node.id + (node.feature ? "\nsplit on " + node.feature : "") +
+    "\nprediction: " + node.prediction + " (" + node.confidence.toFixed(3) + ")" +
+    "\nsamples: " + node.samples + "\nweighted samples: " + node.weighted_samples +
+    "\ngini: " + node.impurity.toFixed(4) + "\ndepth: " + node.depth + "\n" + counts;
+}
+
+function build(node, parent) {
+  const item = document.createElement("li");
+  const span = document.createElement("span");
+  const head = node.edge ? node.edge + " → " : "";
+  span.textContent = head + (node.children.length ? "(" + node.samples + " samples)" :
+    node.prediction + " " + node.confidence.toFixed(2) + " (" + node.samples + " samples)");
+  span.title = describe(node);
+  span.addEventListener("mouseenter", () => { details.textContent = describe(node); });
+  item.appendChild(span);
+  spans.push([span, node, item]);
+  if (node.children.length) {
+    item.className = node.depth >= 3 ? "branch collapsed" : "branch";
+    span.addEventListener("click", () => item.classList.toggle("collapsed"));
+    const list = document.createElement("ul");
+    node.children.forEach(child => build(child, list));
+    item.appendChild(list);
+  } else {
+    item.className = "leaf";
+  }
+  parent.appendChild(item);
+}
+
+function setAll(collapsed) {
+  spans.forEach(([, , item]) => { if (item.classList.contains("branch")) item.classList.toggle("collapsed", collapsed); });
+}
+
+function search(text) {
+  const query = text.trim().toLowerCase();
+  spans.forEach(([span, node, item]) => {
+    const hit = query !== "" && node.edge !== null && node.edge.toLowerCase().includes(query);
+    span.classList.toggle("match", hit);
+    // open every ancestor of a match
+    for (let up = item.parentElement.closest("li"); hit && up; up = up.parentElement.closest("li")) {
+      up.classList.remove("collapsed");
+    }
+  });
+}
+
+build(tree, document.getElementById("tree"));
+document.getElementById("expand").addEventListener("click", () => setAll(false));
+document.getElementById("collapse").addEventListener("click", () => setAll(true));
+document.getElementById("search").addEventListener("input", event => search(event.target.value));
+"#;
+
+impl Tree<Decision> {
+    /// Renders the tree as a single HTML page with collapsible subtrees,
+    /// node statistics on hover and a search box for split features
+    pub fn to_html(&self, title: &str) -> PolarsResult<String> {
+        let root = self
+            .root()
+            .ok_or_else(|| polars_err!(ComputeError: "cannot export an empty tree"))?;
+        let data = serde_json::to_string(&node_json(root, 1, None))
+            .map_err(|e| polars_err!(ComputeError: "cannot serialize the tree: {}", e))?
+            // keeps `</script>` inside labels from closing the data block
+            .replace('<', "\\u003c");
+        let title = escape(title);
+        Ok(format!(
+            concat!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+                "<h1>{}</h1>\n",
+                "<p><input id=\"search\" placeholder=\"search feature\"> ",
+                "<button id=\"expand\">expand all</button> <button id=\"collapse\">collapse all</button></p>\n",
+                "<div id=\"details\">hover a node</div>\n<ul id=\"tree\"></ul>\n",
+                "<script type=\"application/json\" id=\"tree-data\">{}</script>\n",
+                "<script>{}</script>\n</body>\n</html>\n"
+            ),
+            title, STYLE, title, data, SCRIPT
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+
+    fn count(node: &Value) -> usize {
+        1 + node["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(count)
+            .sum::<usize>()
+    }
+
+    #[test]
+    fn embedded_tree() {
+        let data = iris();
+        let features = ["sepal_length", "sepal_width", "petal_length", "petal_width"];
+        let tree = DTreeBuilder::new(features, "variety")
+            .set_max_level(7)
+            .build(&data)
+            .unwrap();
+        let html = tree.to_html("iris <depth 7>").unwrap();
+        assert!(html.contains("<title>iris &lt;depth 7&gt;</title>"));
+        assert_eq!(html.matches("</script>").count(), 2);
+
+        let start = html.find("id=\"tree-data\">").unwrap() + "id=\"tree-data\">".len();
+        let end = start + html[start..].find("</script>").unwrap();
+        let embedded: Value = serde_json::from_str(&html[start..end]).unwrap();
+        assert_eq!(count(&embedded), tree.pre_order_iter().count());
+        assert_eq!(embedded["samples"], 150);
+        assert_eq!(embedded["children"][0]["id"], 2);
+        assert!(embedded["children"][0]["edge"]
+            .as_str()
+            .unwrap()
+            .contains(" > "));
+    }
+}
@@ -0,0 +1,760 @@
+use crate::model::Model;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+// protobuf wire types
+const VARINT: u64 = 0;
+const LENGTH_DELIMITED: u64 = 2;
+const FIXED32: u64 = 5;
+
+// AttributeProto.AttributeType values
+const ATTRIBUTE_INT: i64 = 2;
+const ATTRIBUTE_STRING: i64 = 3;
+const ATTRIBUTE_FLOATS: i64 = 6;
+const ATTRIBUTE_INTS: i64 = 7;
+const ATTRIBUTE_STRINGS: i64 = 8;
+
+// TensorProto.DataType values
+const TENSOR_FLOAT: i64 = 1;
+const TENSOR_STRING: i64 = 8;
+
+const IR_VERSION: i64 = 8;
+const ML_DOMAIN: &str = "ai.onnx.ml";
+
+// minimal protobuf encoder, enough for the onnx messages used here
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn key(&mut self, field: u64, wire: u64) {
+        self.varint((field << 3) | wire);
+    }
+
+    fn int(&mut self, field: u64, value: i64) -> &mut Self {
+        self.key(field, VARINT);
+        self.varint(value as u64);
+        self
+    }
+
+    fn float(&mut self, field: u64, value: f32) -> &mut Self {
+        self.key(field, FIXED32);
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn bytes(&mut self, field: u64, value: &[u8]) -> &mut Self {
+        self.key(field, LENGTH_DELIMITED);
+        self.varint(value.len() as u64);
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    fn string(&mut self, field: u64, value: &str) -> &mut Self {
+        self.bytes(field, value.as_bytes())
+    }
+
+    fn message(&mut self, field: u64, value: &Message) -> &mut Self {
+        self.bytes(field, &value.0)
+    }
+}
+
+fn int_attribute(name: &str, value: i64) -> Message {
+    let mut attribute = Message::default();
+    attribute.string(1, name).int(20, ATTRIBUTE_INT).int(3, value);
+    attribute
+}
+
+fn ints_attribute(name: &str, values: &[i64]) -> Message {
+    let mut attribute = Message::default();
+    attribute.string(1, name).int(20, ATTRIBUTE_INTS);
+    for value in values {
+        attribute.int(8, *value);
+    }
+    attribute
+}
+
+fn floats_attribute(name: &str, values: &[f32]) -> Message {
+    let mut attribute = Message::default();
+    attribute.string(1, name).int(20, ATTRIBUTE_FLOATS);
+    for value in values {
+        attribute.float(7, *value);
+    }
+    attribute
+}
+
+fn strings_attribute(name: &str, values: &[String]) -> Message {
+    let mut attribute = Message::default();
+    attribute.string(1, name).int(20, ATTRIBUTE_STRINGS);
+    for value in values {
+        attribute.string(9, value);
+    }
+    attribute
+}
+
+fn string_attribute(name: &str, value: &str) -> Message {
+    let mut attribute = Message::default();
+    attribute.string(1, name).int(20, ATTRIBUTE_STRING).string(4, value);
+    attribute
+}
+
+// ValueInfoProto of a tensor whose first dimension is the batch size
+fn tensor_info(name: &str, elem_type: i64, columns: Option<usize>) -> Message {
+    let mut shape = Message::default();
+    let mut batch = Message::default();
+    batch.string(2, "N");
+    shape.message(1, &batch);
+    if let Some(columns) = columns {
+        let mut dim = Message::default();
+        dim.int(1, columns as i64);
+        shape.message(1, &dim);
+    }
+    let mut tensor = Message::default();
+    tensor.int(1, elem_type).message(2, &shape);
+    let mut kind = Message::default();
+    kind.message(1, &tensor);
+    let mut info = Message::default();
+    info.string(1, name).message(2, &kind);
+    info
+}
+
+// the largest f32 that is not above `cutoff`: for every f32 input `x`,
+// `x > threshold(cutoff)` holds exactly when `x as f64 > cutoff`
+fn threshold(cutoff: f64) -> f32 {
+    let rounded = cutoff as f32;
+    if !rounded.is_finite() || rounded as f64 <= cutoff {
+        return rounded;
+    }
+    // step one unit in the last place towards negative infinity
+    if rounded == 0.0 {
+        -f32::from_bits(1)
+    } else if rounded > 0.0 {
+        f32::from_bits(rounded.to_bits() - 1)
+    } else {
+        f32::from_bits(rounded.to_bits() + 1)
+    }
+}
+
+// ModelProto with a single operator node in its graph
+fn model_bytes(node: &Message, inputs: usize, outputs: &[Message]) -> Vec<u8> {
+    let mut graph = Message::default();
+    graph
+        .message(1, node)
+        .string(2, "decision_tree")
+        .message(11, &tensor_info("X", TENSOR_FLOAT, Some(inputs)));
+    for output in outputs {
+        graph.message(12, output);
+    }
+    let mut ml = Message::default();
+    ml.string(1, ML_DOMAIN).int(2, 3);
+    let mut default = Message::default();
+    default.string(1, "").int(2, 17);
+    let mut model = Message::default();
+    model
+        .int(1, IR_VERSION)
+        .string(2, env!("CARGO_PKG_NAME"))
+        .string(3, env!("CARGO_PKG_VERSION"))
+        .message(7, &graph)
+        .message(8, &default)
+        .message(8, &ml);
+    model.0
+}
+
+// the node arrays shared by both operators
+struct NodeArrays<'a> {
+    treeids: &'a [i64],
+    nodeids: &'a [i64],
+    featureids: &'a [i64],
+    values: &'a [f32],
+    modes: &'a [String],
+    truenodeids: &'a [i64],
+    falsenodeids: &'a [i64],
+    missing_value_tracks_true: &'a [i64],
+}
+
+impl NodeArrays<'_> {
+    // tree id and leaf id reached in every tree of the ensemble, following
+    // the onnxruntime semantics
+    fn leaves(&self, row: &[f32]) -> PolarsResult<Vec<(i64, i64)>> {
+        let size = self.treeids.len();
+        polars_ensure!(
+            [
+                self.nodeids.len(),
+                self.featureids.len(),
+                self.values.len(),
+                self.modes.len(),
+                self.truenodeids.len(),
+                self.falsenodeids.len(),
+                self.missing_value_tracks_true.len(),
+            ]
+            .iter()
+            .all(|len| *len == size),
+            ShapeMismatch: "the node arrays of the operator differ in length"
+        );
+        let mut index: HashMap<(i64, i64), usize> = HashMap::new();
+        let mut roots: Vec<usize> = Vec::new();
+        for (i, key) in self.treeids.iter().zip(self.nodeids).enumerate() {
+            // the first node listed for each tree is its root
+            if !roots.iter().any(|r| self.treeids[*r] == *key.0) {
+                roots.push(i);
+            }
+            index.insert((*key.0, *key.1), i);
+        }
+        let mut leaves = Vec::with_capacity(roots.len());
+        for root in roots {
+            let tree = self.treeids[root];
+            let mut node = root;
+            // a well formed tree reaches a leaf before visiting every node
+            let mut steps = 0;
+            while self.modes[node] != "LEAF" {
+                steps += 1;
+                polars_ensure!(steps <= size, ComputeError: "tree {} has a cycle", tree);
+                let feature = self.featureids[node];
+                let x = *usize::try_from(feature).ok().and_then(|f| row.get(f)).ok_or_else(
+                    || polars_err!(OutOfBounds: "feature {} is out of range for {} inputs", feature, row.len()),
+                )?;
+                let threshold = self.values[node];
+                let condition = if x.is_nan() {
+                    self.missing_value_tracks_true[node] != 0
+                } else {
+                    match self.modes[node].as_str() {
+                        "BRANCH_LEQ" => x <= threshold,
+                        "BRANCH_LT" => x < threshold,
+                        "BRANCH_GTE" => x >= threshold,
+                        "BRANCH_GT" => x > threshold,
+                        "BRANCH_EQ" => x == threshold,
+                        "BRANCH_NEQ" => x != threshold,
+                        mode => polars_bail!(ComputeError: "unknown node mode `{}`", mode),
+                    }
+                };
+                let next = if condition {
+                    self.truenodeids[node]
+                } else {
+                    self.falsenodeids[node]
+                };
+                node = *index
+                    .get(&(tree, next))
+                    .ok_or_else(|| polars_err!(ComputeError: "tree {} has no node {}", tree, next))?;
+            }
+            leaves.push((tree, self.nodeids[node]));
+        }
+        Ok(leaves)
+    }
+}
+
+// sums the weights attached to the leaves reached, one score per output id
+fn leaf_scores(
+    leaves: &[(i64, i64)],
+    treeids: &[i64],
+    nodeids: &[i64],
+    ids: &[i64],
+    weights: &[f32],
+    outputs: usize,
+) -> PolarsResult<Vec<f32>> {
+    polars_ensure!(
+        nodeids.len() == treeids.len() && ids.len() == treeids.len() && weights.len() == treeids.len(),
+        ShapeMismatch: "the leaf weight arrays of the operator differ in length"
+    );
+    let mut scores = vec![0.0f32; outputs];
+    for i in 0..treeids.len() {
+        if leaves.contains(&(treeids[i], nodeids[i])) {
+            let score = usize::try_from(ids[i])
+                .ok()
+                .and_then(|id| scores.get_mut(id))
+                .ok_or_else(|| polars_err!(OutOfBounds: "output {} is out of range for {} outputs", ids[i], outputs))?;
+            *score += weights[i];
+        }
+    }
+    Ok(scores)
+}
+
+/// Attributes of the ONNX-ML `TreeEnsembleClassifier` operator; node ids
+/// are the heap ids of `PreOrderTraversalIter`, the true branch of each
+/// `BRANCH_GT` node is the `>` child and missing values take the false one
+///
+/// Thresholds are f32 in ONNX: each cutoff is rounded down to the nearest
+/// f32, so that f32 inputs branch as they do in the tree. Features stored
+/// as f64 closer to a cutoff than f32 precision can still land on the other
+/// side once converted to f32 inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeEnsembleClassifier {
+    pub features: Vec<String>,
+    pub classlabels_strings: Vec<String>,
+    pub nodes_treeids: Vec<i64>,
+    pub nodes_nodeids: Vec<i64>,
+    pub nodes_featureids: Vec<i64>,
+    pub nodes_values: Vec<f32>,
+    pub nodes_modes: Vec<String>,
+    pub nodes_truenodeids: Vec<i64>,
+    pub nodes_falsenodeids: Vec<i64>,
+    pub nodes_missing_value_tracks_true: Vec<i64>,
+    pub nodes_hitrates: Vec<f32>,
+    pub class_treeids: Vec<i64>,
+    pub class_nodeids: Vec<i64>,
+    pub class_ids: Vec<i64>,
+    pub class_weights: Vec<f32>,
+    pub post_transform: String,
+}
+
+impl TreeEnsembleClassifier {
+    /// Maps trees sharing features and classes into the operator arrays;
+    /// leaf probabilities are divided by the number of trees so that the
+    /// summed scores average the ensemble
+    pub fn from_models(models: &[Model]) -> PolarsResult<TreeEnsembleClassifier> {
+        let first = models
+            .first()
+            .ok_or_else(|| polars_err!(ComputeError: "cannot export an empty ensemble"))?;
+        for model in models {
+            polars_ensure!(
+                model.features == first.features && model.classes == first.classes,
+                SchemaMismatch: "all the trees of an ensemble must share features and classes"
+            );
+        }
+        let features: HashMap<&str, i64> = first
+            .features
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (f.as_str(), i as i64))
+            .collect();
+        let share = 1.0 / models.len() as f32;
+        let mut operator = TreeEnsembleClassifier {
+            features: first.features.clone(),
+            classlabels_strings: first.classes.clone(),
+            nodes_treeids: Vec::new(),
+            nodes_nodeids: Vec::new(),
+            nodes_featureids: Vec::new(),
+            nodes_values: Vec::new(),
+            nodes_modes: Vec::new(),
+            nodes_truenodeids: Vec::new(),
+            nodes_falsenodeids: Vec::new(),
+            nodes_missing_value_tracks_true: Vec::new(),
+            nodes_hitrates: Vec::new(),
+            class_treeids: Vec::new(),
+            class_nodeids: Vec::new(),
+            class_ids: Vec::new(),
+            class_weights: Vec::new(),
+            post_transform: "NONE".into(),
+        };
+        for (tree_id, model) in models.iter().enumerate() {
+            let tree_id = tree_id as i64;
+            for item in model.tree.pre_order_iter() {
+                let id = item.id as i64;
+                operator.nodes_treeids.push(tree_id);
+                operator.nodes_nodeids.push(id);
+                operator.nodes_missing_value_tracks_true.push(0);
+                operator.nodes_hitrates.push(1.0);
+                match item.value.rule() {
+                    Some(rule) if !item.leaf => {
+                        let feature = *features.get(rule.dimension()).ok_or_else(
+                            || polars_err!(ColumnNotFound: "the tree splits on unknown feature `{}`", rule.dimension()),
+                        )?;
+                        operator.nodes_featureids.push(feature);
+                        operator.nodes_values.push(threshold(rule.cutoff()));
+                        operator.nodes_modes.push("BRANCH_GT".into());
+                        operator.nodes_truenodeids.push(id << 1);
+                        operator.nodes_falsenodeids.push((id << 1) + 1);
+                    }
+                    _ => {
+                        operator.nodes_featureids.push(0);
+                        operator.nodes_values.push(0.0);
+                        operator.nodes_modes.push("LEAF".into());
+                        operator.nodes_truenodeids.push(0);
+                        operator.nodes_falsenodeids.push(0);
+                        for (class_id, class) in first.classes.iter().enumerate() {
+                            operator.class_treeids.push(tree_id);
+                            operator.class_nodeids.push(id);
+                            operator.class_ids.push(class_id as i64);
+                            operator.class_weights.push(item.value.probability(class) as f32 * share);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(operator)
+    }
+
+    fn nodes(&self) -> NodeArrays<'_> {
+        NodeArrays {
+            treeids: &self.nodes_treeids,
+            nodeids: &self.nodes_nodeids,
+            featureids: &self.nodes_featureids,
+            values: &self.nodes_values,
+            modes: &self.nodes_modes,
+            truenodeids: &self.nodes_truenodeids,
+            falsenodeids: &self.nodes_falsenodeids,
+            missing_value_tracks_true: &self.nodes_missing_value_tracks_true,
+        }
+    }
+
+    /// Reference evaluation of the operator on one row, following the
+    /// onnxruntime semantics: returns the label index and the class scores
+    pub fn evaluate(&self, row: &[f32]) -> PolarsResult<(usize, Vec<f32>)> {
+        let leaves = self.nodes().leaves(row)?;
+        let scores = leaf_scores(
+            &leaves,
+            &self.class_treeids,
+            &self.class_nodeids,
+            &self.class_ids,
+            &self.class_weights,
+            self.classlabels_strings.len(),
+        )?;
+        let label = scores
+            .iter()
+            .enumerate()
+            .fold(0, |best, (i, s)| if *s > scores[best] { i } else { best });
+        Ok((label, scores))
+    }
+
+    fn node(&self) -> Message {
+        let mut node = Message::default();
+        node.string(1, "X")
+            .string(2, "label")
+            .string(2, "probabilities")
+            .string(3, "TreeEnsembleClassifier")
+            .string(4, "TreeEnsembleClassifier")
+            .string(7, ML_DOMAIN);
+        let attributes = [
+            strings_attribute("classlabels_strings", &self.classlabels_strings),
+            ints_attribute("nodes_treeids", &self.nodes_treeids),
+            ints_attribute("nodes_nodeids", &self.nodes_nodeids),
+            ints_attribute("nodes_featureids", &self.nodes_featureids),
+            floats_attribute("nodes_values", &self.nodes_values),
+            strings_attribute("nodes_modes", &self.nodes_modes),
+            ints_attribute("nodes_truenodeids", &self.nodes_truenodeids),
+            ints_attribute("nodes_falsenodeids", &self.nodes_falsenodeids),
+            ints_attribute("nodes_missing_value_tracks_true", &self.nodes_missing_value_tracks_true),
+            floats_attribute("nodes_hitrates", &self.nodes_hitrates),
+            ints_attribute("class_treeids", &self.class_treeids),
+            ints_attribute("class_nodeids", &self.class_nodeids),
+            ints_attribute("class_ids", &self.class_ids),
+            floats_attribute("class_weights", &self.class_weights),
+            string_attribute("post_transform", &self.post_transform),
+        ];
+        for attribute in &attributes {
+            node.message(5, attribute);
+        }
+        node
+    }
+
+    /// Serialises the operator as a complete ONNX `ModelProto`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let outputs = [
+            tensor_info("label", TENSOR_STRING, None),
+            tensor_info("probabilities", TENSOR_FLOAT, Some(self.classlabels_strings.len())),
+        ];
+        model_bytes(&self.node(), self.features.len(), &outputs)
+    }
+}
+
+/// Attributes of the ONNX-ML `TreeEnsembleRegressor` operator, with one
+/// target per class holding its leaf probability; nodes are laid out as in
+/// `TreeEnsembleClassifier`, for runtimes or pipelines that only take
+/// regressors
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeEnsembleRegressor {
+    pub features: Vec<String>,
+    // class of each target
+    pub targets: Vec<String>,
+    pub n_targets: i64,
+    pub nodes_treeids: Vec<i64>,
+    pub nodes_nodeids: Vec<i64>,
+    pub nodes_featureids: Vec<i64>,
+    pub nodes_values: Vec<f32>,
+    pub nodes_modes: Vec<String>,
+    pub nodes_truenodeids: Vec<i64>,
+    pub nodes_falsenodeids: Vec<i64>,
+    pub nodes_missing_value_tracks_true: Vec<i64>,
+    pub nodes_hitrates: Vec<f32>,
+    pub target_treeids: Vec<i64>,
+    pub target_nodeids: Vec<i64>,
+    pub target_ids: Vec<i64>,
+    pub target_weights: Vec<f32>,
+    pub aggregate_function: String,
+    pub post_transform: String,
+}
+
+impl TreeEnsembleRegressor {
+    /// Maps trees sharing features and classes into the operator arrays;
+    /// the summed targets average the class probabilities of the ensemble
+    pub fn from_models(models: &[Model]) -> PolarsResult<TreeEnsembleRegressor> {
+        let classifier = TreeEnsembleClassifier::from_models(models)?;
+        Ok(TreeEnsembleRegressor {
+            features: classifier.features,
+            n_targets: classifier.classlabels_strings.len() as i64,
+            targets: classifier.classlabels_strings,
+            nodes_treeids: classifier.nodes_treeids,
+            nodes_nodeids: classifier.nodes_nodeids,
+            nodes_featureids: classifier.nodes_featureids,
+            nodes_values: classifier.nodes_values,
+            nodes_modes: classifier.nodes_modes,
+            nodes_truenodeids: classifier.nodes_truenodeids,
+            nodes_falsenodeids: classifier.nodes_falsenodeids,
+            nodes_missing_value_tracks_true: classifier.nodes_missing_value_tracks_true,
+            nodes_hitrates: classifier.nodes_hitrates,
+            target_treeids: classifier.class_treeids,
+            target_nodeids: classifier.class_nodeids,
+            target_ids: classifier.class_ids,
+            target_weights: classifier.class_weights,
+            aggregate_function: "SUM".into(),
+            post_transform: classifier.post_transform,
+        })
+    }
+
+    fn nodes(&self) -> NodeArrays<'_> {
+        NodeArrays {
+            treeids: &self.nodes_treeids,
+            nodeids: &self.nodes_nodeids,
+            featureids: &self.nodes_featureids,
+            values: &self.nodes_values,
+            modes: &self.nodes_modes,
+            truenodeids: &self.nodes_truenodeids,
+            falsenodeids: &self.nodes_falsenodeids,
+            missing_value_tracks_true: &self.nodes_missing_value_tracks_true,
+        }
+    }
+
+    /// Reference evaluation of the operator on one row: the summed target
+    /// weights of the leaves reached
+    pub fn evaluate(&self, row: &[f32]) -> PolarsResult<Vec<f32>> {
+        polars_ensure!(
+            self.aggregate_function == "SUM",
+            ComputeError: "only the SUM aggregate is evaluated, not `{}`", self.aggregate_function
+        );
+        let outputs = usize::try_from(self.n_targets)
+            .map_err(|_| polars_err!(ComputeError: "invalid number of targets {}", self.n_targets))?;
+        let leaves = self.nodes().leaves(row)?;
+        leaf_scores(
+            &leaves,
+            &self.target_treeids,
+            &self.target_nodeids,
+            &self.target_ids,
+            &self.target_weights,
+            outputs,
+        )
+    }
+
+    fn node(&self) -> Message {
+        let mut node = Message::default();
+        node.string(1, "X")
+            .string(2, "Y")
+            .string(3, "TreeEnsembleRegressor")
+            .string(4, "TreeEnsembleRegressor")
+            .string(7, ML_DOMAIN);
+        let attributes = [
+            int_attribute("n_targets", self.n_targets),
+            ints_attribute("nodes_treeids", &self.nodes_treeids),
+            ints_attribute("nodes_nodeids", &self.nodes_nodeids),
+            ints_attribute("nodes_featureids", &self.nodes_featureids),
+            floats_attribute("nodes_values", &self.nodes_values),
+            strings_attribute("nodes_modes", &self.nodes_modes),
+            ints_attribute("nodes_truenodeids", &self.nodes_truenodeids),
+            ints_attribute("nodes_falsenodeids", &self.nodes_falsenodeids),
+            ints_attribute("nodes_missing_value_tracks_true", &self.nodes_missing_value_tracks_true),
+            floats_attribute("nodes_hitrates", &self.nodes_hitrates),
+            ints_attribute("target_treeids", &self.target_treeids),
+            ints_attribute("target_nodeids", &self.target_nodeids),
+            ints_attribute("target_ids", &self.target_ids),
+            floats_attribute("target_weights", &self.target_weights),
+            string_attribute("aggregate_function", &self.aggregate_function),
+            string_attribute("post_transform", &self.post_transform),
+        ];
+        for attribute in &attributes {
+            node.message(5, attribute);
+        }
+        node
+    }
+
+    /// Serialises the operator as a complete ONNX `ModelProto`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let outputs = [tensor_info("Y", TENSOR_FLOAT, Some(self.targets.len()))];
+        model_bytes(&self.node(), self.features.len(), &outputs)
+    }
+}
+
+impl Model {
+    /// Exports the tree as an ONNX model holding a single
+    /// `TreeEnsembleClassifier`; input `X` takes the features in the
+    /// order of `Model::features`
+    pub fn to_onnx(&self) -> PolarsResult<Vec<u8>> {
+        ensemble_to_onnx(std::slice::from_ref(self))
+    }
+
+    /// Exports the tree as an ONNX `TreeEnsembleRegressor` whose output `Y`
+    /// holds the class probabilities in the order of `Model::classes`
+    pub fn to_onnx_regressor(&self) -> PolarsResult<Vec<u8>> {
+        ensemble_to_onnx_regressor(std::slice::from_ref(self))
+    }
+}
+
+/// Exports trees sharing features and classes as one ONNX classifier
+pub fn ensemble_to_onnx(models: &[Model]) -> PolarsResult<Vec<u8>> {
+    Ok(TreeEnsembleClassifier::from_models(models)?.to_bytes())
+}
+
+/// Exports trees sharing features and classes as one ONNX regressor of
+/// the averaged class probabilities
+pub fn ensemble_to_onnx_regressor(models: &[Model]) -> PolarsResult<Vec<u8>> {
+    Ok(TreeEnsembleRegressor::from_models(models)?.to_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use onnx_protobuf::{AttributeProto, Message as _, ModelProto, NodeProto};
+    use std::collections::HashSet;
+
+    fn model() -> (DataFrame, Model) {
+        let data = iris();
+        let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
+        let model = DTreeBuilder::new(features, "variety")
+            .set_max_level(4)
+            .fit(&data)
+            .unwrap();
+        (data, model)
+    }
+
+    fn rows(data: &DataFrame, model: &Model) -> Vec<Vec<f32>> {
+        let columns: Vec<Vec<f32>> = model
+            .features
+            .iter()
+            .map(|f| {
+                let values = data.column(f).unwrap().cast(&DataType::Float32).unwrap();
+                values.f32().unwrap().into_no_null_iter().collect()
+            })
+            .collect();
+        (0..data.height())
+            .map(|i| columns.iter().map(|c| c[i]).collect())
+            .collect()
+    }
+
+    // the single operator node of an exported model, decoded with the
+    // protobuf definitions of the onnx project
+    fn decode(bytes: &[u8]) -> NodeProto {
+        let model = ModelProto::parse_from_bytes(bytes).unwrap();
+        assert_eq!(model.ir_version, IR_VERSION);
+        assert!(model.opset_import.iter().any(|o| o.domain == ML_DOMAIN && o.version == 3));
+        let graph = model.graph.unwrap();
+        assert_eq!(graph.input[0].name, "X");
+        assert_eq!(graph.node.len(), 1);
+        graph.node[0].clone()
+    }
+
+    fn attribute<'a>(node: &'a NodeProto, name: &str) -> &'a AttributeProto {
+        node.attribute.iter().find(|a| a.name == name).unwrap()
+    }
+
+    fn strings(node: &NodeProto, name: &str) -> Vec<String> {
+        let values = &attribute(node, name).strings;
+        values.iter().map(|s| String::from_utf8(s.clone()).unwrap()).collect()
+    }
+
+    fn text(node: &NodeProto, name: &str) -> String {
+        String::from_utf8(attribute(node, name).s.clone()).unwrap()
+    }
+
+    #[test]
+    fn reference_evaluator_agrees() {
+        let (data, model) = model();
+        let operator = TreeEnsembleClassifier::from_models(std::slice::from_ref(&model)).unwrap();
+        let predictions = model.predict(&data).unwrap();
+        let predictions = predictions.str().unwrap();
+        for (i, row) in rows(&data, &model).iter().enumerate() {
+            let (label, scores) = operator.evaluate(row).unwrap();
+            assert_eq!(Some(operator.classlabels_strings[label].as_str()), predictions.get(i));
+            assert!((scores.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        }
+
+        let mut broken = operator.clone();
+        broken.nodes_truenodeids[0] = 1000;
+        assert!(broken.evaluate(&rows(&data, &model)[100]).is_err());
+        assert!(operator.evaluate(&[]).is_err());
+    }
+
+    #[test]
+    fn decoded_graphs_predict_like_the_model() {
+        let (data, model) = model();
+        let predictions = model.predict(&data).unwrap();
+        let predictions = predictions.str().unwrap();
+        let probabilities = model.tree.predict_proba(&data).unwrap();
+
+        let node = decode(&model.to_onnx().unwrap());
+        assert_eq!((node.op_type.as_str(), node.domain.as_str()), ("TreeEnsembleClassifier", ML_DOMAIN));
+        assert_eq!(node.output, ["label", "probabilities"]);
+        let classifier = TreeEnsembleClassifier {
+            features: model.features.clone(),
+            classlabels_strings: strings(&node, "classlabels_strings"),
+            nodes_treeids: attribute(&node, "nodes_treeids").ints.clone(),
+            nodes_nodeids: attribute(&node, "nodes_nodeids").ints.clone(),
+            nodes_featureids: attribute(&node, "nodes_featureids").ints.clone(),
+            nodes_values: attribute(&node, "nodes_values").floats.clone(),
+            nodes_modes: strings(&node, "nodes_modes"),
+            nodes_truenodeids: attribute(&node, "nodes_truenodeids").ints.clone(),
+            nodes_falsenodeids: attribute(&node, "nodes_falsenodeids").ints.clone(),
+            nodes_missing_value_tracks_true: attribute(&node, "nodes_missing_value_tracks_true").ints.clone(),
+            nodes_hitrates: attribute(&node, "nodes_hitrates").floats.clone(),
+            class_treeids: attribute(&node, "class_treeids").ints.clone(),
+            class_nodeids: attribute(&node, "class_nodeids").ints.clone(),
+            class_ids: attribute(&node, "class_ids").ints.clone(),
+            class_weights: attribute(&node, "class_weights").floats.clone(),
+            post_transform: text(&node, "post_transform"),
+        };
+        assert_eq!(classifier, TreeEnsembleClassifier::from_models(std::slice::from_ref(&model)).unwrap());
+        for (i, row) in rows(&data, &model).iter().enumerate() {
+            let (label, _) = classifier.evaluate(row).unwrap();
+            assert_eq!(Some(classifier.classlabels_strings[label].as_str()), predictions.get(i));
+        }
+
+        let node = decode(&model.to_onnx_regressor().unwrap());
+        assert_eq!(node.op_type, "TreeEnsembleRegressor");
+        assert_eq!(node.output, ["Y"]);
+        let regressor = TreeEnsembleRegressor {
+            features: model.features.clone(),
+            targets: model.classes.clone(),
+            n_targets: attribute(&node, "n_targets").i,
+            nodes_treeids: attribute(&node, "nodes_treeids").ints.clone(),
+            nodes_nodeids: attribute(&node, "nodes_nodeids").ints.clone(),
+            nodes_featureids: attribute(&node, "nodes_featureids").ints.clone(),
+            nodes_values: attribute(&node, "nodes_values").floats.clone(),
+            nodes_modes: strings(&node, "nodes_modes"),
+            nodes_truenodeids: attribute(&node, "nodes_truenodeids").ints.clone(),
+            nodes_falsenodeids: attribute(&node, "nodes_falsenodeids").ints.clone(),
+            nodes_missing_value_tracks_true: attribute(&node, "nodes_missing_value_tracks_true").ints.clone(),
+            nodes_hitrates: attribute(&node, "nodes_hitrates").floats.clone(),
+            target_treeids: attribute(&node, "target_treeids").ints.clone(),
+            target_nodeids: attribute(&node, "target_nodeids").ints.clone(),
+            target_ids: attribute(&node, "target_ids").ints.clone(),
+            target_weights: attribute(&node, "target_weights").floats.clone(),
+            aggregate_function: text(&node, "aggregate_function"),
+            post_transform: text(&node, "post_transform"),
+        };
+        assert_eq!(regressor.n_targets, 3);
+        for (i, row) in rows(&data, &model).iter().enumerate() {
+            let scores = regressor.evaluate(row).unwrap();
+            for (class, score) in model.classes.iter().zip(scores) {
+                let expected = probabilities.column(class).unwrap().f64().unwrap().get(i).unwrap();
+                assert!((score as f64 - expected).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn thresholds_keep_comparisons() {
+        for cutoff in [0.1, -0.1, 2.45, 1.0, 0.0, 1e-40, -1e-40] {
+            let rounded = threshold(cutoff);
+            assert!(rounded as f64 <= cutoff);
+            let next = f32::from_bits(rounded.to_bits().wrapping_add(if rounded >= 0.0 { 1 } else { u32::MAX }));
+            for x in [rounded, next, cutoff as f32] {
+                assert_eq!(x > rounded, x as f64 > cutoff, "{} against {}", x, cutoff);
+            }
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use crate::btree::Node;
+use crate::model::Model;
+use crate::plot::escape;
+use crate::Decision;
+use std::fmt::Write;
+
+// score distribution of every class seen in the node
+fn write_distribution(xml: &mut String, decision: &Decision, indent: &str) {
+    for (class, count) in decision.distribution() {
+        let _ = writeln!(
+            xml,
+            "{}<ScoreDistribution value=\"{}\" recordCount=\"{}\" probability=\"{}\"/>",
+            indent,
+            escape(class),
+            count,
+            decision.probability(class)
+        );
+    }
+}
+
+// writes a node and its subtree; ids follow the heap numbering of
+// `PreOrderTraversalIter`, missing values go to the `<=` child
+fn write_node(xml: &mut String, node: &Node<Decision>, id: usize, predicate: &str, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let decision = &node.value;
+    let split = match (decision.rule(), &node.left, &node.right) {
+        (Some(rule), Some(left), Some(right)) => Some((rule, left, right)),
+        _ => None,
+    };
+    let default_child = match split {
+        Some(_) => format!(" defaultChild=\"{}\"", (id << 1) + 1),
+        None => String::new(),
+    };
+    let _ = writeln!(
+        xml,
+        "{}<Node id=\"{}\" score=\"{}\" recordCount=\"{}\"{}>",
+        indent,
+        id,
+        escape(decision.prediction()),
+        decision.samples(),
+        default_child
+    );
+    let _ = writeln!(xml, "{}  {}", indent, predicate);
+    write_distribution(xml, decision, &format!("{}  ", indent));
+    if let Some((rule, left, right)) = split {
+        let field = escape(rule.dimension());
+        let higher = format!(
+            "<SimplePredicate field=\"{}\" operator=\"greaterThan\" value=\"{}\"/>",
+            field,
+            rule.cutoff()
+        );
+        let lower = format!(
+            "<SimplePredicate field=\"{}\" operator=\"lessOrEqual\" value=\"{}\"/>",
+            field,
+            rule.cutoff()
+        );
+        write_node(xml, left, id << 1, &higher, depth + 1);
+        write_node(xml, right, (id << 1) + 1, &lower, depth + 1);
+    }
+    let _ = writeln!(xml, "{}</Node>", indent);
+}
+
+impl Model {
+    /// Exports the tree as a PMML 4.4 `TreeModel` document
+    pub fn to_pmml(&self) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<PMML xmlns=\"http://www.dmg.org/PMML-4_4\" version=\"4.4\">\n");
+        let _ = writeln!(
+            xml,
+            "  <Header description=\"decision tree\">\n    <Application name=\"{}\" version=\"{}\"/>\n  </Header>",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        );
+
+        let _ = writeln!(xml, "  <DataDictionary numberOfFields=\"{}\">", self.features.len() + 1);
+        for feature in &self.features {
+            let _ = writeln!(
+                xml,
+                "    <DataField name=\"{}\" optype=\"continuous\" dataType=\"double\"/>",
+                escape(feature)
+            );
+        }
+        let _ = writeln!(
+            xml,
+            "    <DataField name=\"{}\" optype=\"categorical\" dataType=\"string\">",
+            escape(&self.target)
+        );
+        for class in &self.classes {
+            let _ = writeln!(xml, "      <Value value=\"{}\"/>", escape(class));
+        }
+        xml.push_str("    </DataField>\n  </DataDictionary>\n");
+
+        xml.push_str(
+            "  <TreeModel functionName=\"classification\" splitCharacteristic=\"binarySplit\" \
+             missingValueStrategy=\"defaultChild\" noTrueChildStrategy=\"returnLastPrediction\">\n",
+        );
+        xml.push_str("    <MiningSchema>\n");
+        for feature in &self.features {
+            let _ = writeln!(xml, "      <MiningField name=\"{}\"/>", escape(feature));
+        }
+        let _ = writeln!(
+            xml,
+            "      <MiningField name=\"{}\" usageType=\"target\"/>",
+            escape(&self.target)
+        );
+        xml.push_str("    </MiningSchema>\n");
+        if let Some(root) = self.tree.root() {
+            write_node(&mut xml, root, 1, "<True/>", 2);
+        }
+        xml.push_str("  </TreeModel>\n</PMML>\n");
+        xml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use polars::prelude::*;
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+    use std::collections::{HashMap, HashSet};
+
+    // a node read back from the document
+    #[derive(Default)]
+    struct Parsed {
+        score: String,
+        predicate: Option<(String, String, f64)>,
+        // probability of each class in the score distribution
+        probabilities: HashMap<String, f64>,
+        children: Vec<Parsed>,
+    }
+
+    fn attributes(element: &quick_xml::events::BytesStart) -> HashMap<String, String> {
+        element
+            .attributes()
+            .flatten()
+            .map(|a| {
+                (
+                    String::from_utf8(a.key.as_ref().to_vec()).unwrap(),
+                    a.unescape_value().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn parse(xml: &str) -> Parsed {
+        let mut reader = Reader::from_str(xml);
+        let mut stack: Vec<Parsed> = vec![Parsed::default()];
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(e) if e.name().as_ref() == b"Node" => {
+                    stack.push(Parsed {
+                        score: attributes(&e)["score"].clone(),
+                        ..Default::default()
+                    });
+                }
+                Event::Empty(e) if e.name().as_ref() == b"SimplePredicate" => {
+                    let a = attributes(&e);
+                    stack.last_mut().unwrap().predicate =
+                        Some((a["field"].clone(), a["operator"].clone(), a["value"].parse().unwrap()));
+                }
+                Event::Empty(e) if e.name().as_ref() == b"ScoreDistribution" => {
+                    let a = attributes(&e);
+                    let probability = a["probability"].parse().unwrap();
+                    stack.last_mut().unwrap().probabilities.insert(a["value"].clone(), probability);
+                }
+                Event::End(e) if e.name().as_ref() == b"Node" => {
+                    let node = stack.pop().unwrap();
+                    stack.last_mut().unwrap().children.push(node);
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        stack.pop().unwrap().children.pop().unwrap()
+    }
+
+    // follows the first child whose predicate holds down to a leaf
+    fn score<'a>(node: &'a Parsed, row: &HashMap<&str, f64>) -> &'a Parsed {
+        for child in &node.children {
+            let (field, operator, value) = child.predicate.as_ref().unwrap();
+            let x = row[field.as_str()];
+            let holds = match operator.as_str() {
+                "greaterThan" => x > *value,
+                "lessOrEqual" => x <= *value,
+                _ => panic!("unexpected operator {}", operator),
+            };
+            if holds {
+                return score(child, row);
+            }
+        }
+        node
+    }
+
+    #[test]
+    fn pmml_predictions_agree() {
+        let data = iris();
+        let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
+        let model = DTreeBuilder::new(features, "variety")
+            .set_max_level(5)
+            .fit(&data)
+            .unwrap();
+        let xml = model.to_pmml();
+        assert!(xml.contains("<DataField name=\"variety\" optype=\"categorical\" dataType=\"string\">"));
+        assert!(!xml.contains("confidence="));
+        let root = parse(&xml);
+
+        let predictions = model.predict(&data).unwrap();
+        let predictions = predictions.str().unwrap();
+        let probabilities = model.tree.predict_proba(&data).unwrap();
+        let columns: Vec<(&str, Vec<f64>)> = model
+            .features
+            .iter()
+            .map(|f| {
+                let values = data.column(f).unwrap().cast(&DataType::Float64).unwrap();
+                (f.as_str(), values.f64().unwrap().into_no_null_iter().collect())
+            })
+            .collect();
+        for i in 0..data.height() {
+            let row: HashMap<&str, f64> = columns.iter().map(|(name, values)| (*name, values[i])).collect();
+            let leaf = score(&root, &row);
+            assert_eq!(Some(leaf.score.as_str()), predictions.get(i));
+            assert!(!leaf.probabilities.is_empty());
+            for (class, probability) in &leaf.probabilities {
+                let expected = probabilities.column(class).unwrap().f64().unwrap().get(i);
+                assert_eq!(Some(*probability), expected);
+            }
+        }
+    }
+}
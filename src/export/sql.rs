@@ -0,0 +1,247 @@
+use crate::btree::{Node, Tree};
+use crate::Decision;
+use polars::prelude::*;
+use std::fmt::Write;
+
+// reserved words of SQL:2016 and of the supported dialects, sorted for
+// binary search; they are quoted even when they look like plain identifiers
+const RESERVED: &[&str] = &[
+    "abs", "all", "allocate", "alter", "analyse", "analyze", "and", "any", "are", "array", "as",
+    "asc", "asensitive", "asymmetric", "at", "atomic", "authorization", "avg", "begin", "between",
+    "bigint", "binary", "blob", "boolean", "both", "by", "call", "called", "cascaded", "case",
+    "cast", "char", "character", "check", "clob", "close", "collate", "column", "commit",
+    "condition", "connect", "constraint", "continue", "convert", "corresponding", "count", "create",
+    "cross", "cube", "current", "current_date", "current_time", "current_timestamp", "current_user",
+    "cursor", "cycle", "date", "day", "deallocate", "dec", "decimal", "declare", "default",
+    "delete", "deref", "describe", "deterministic", "disconnect", "distinct", "do", "double",
+    "drop", "dynamic", "each", "element", "else", "end", "escape", "every", "except", "exec",
+    "execute", "exists", "external", "extract", "false", "fetch", "filter", "float", "for",
+    "foreign", "free", "from", "full", "function", "fusion", "get", "global", "grant", "group",
+    "grouping", "having", "hold", "hour", "identity", "in", "indicator", "inner", "inout",
+    "insensitive", "insert", "int", "integer", "intersect", "interval", "into", "is", "join",
+    "lateral", "leading", "left", "like", "limit", "local", "localtime", "localtimestamp", "lower",
+    "match", "max", "member", "merge", "method", "min", "minute", "modifies", "module", "month",
+    "multiset", "national", "natural", "nchar", "nclob", "new", "no", "none", "normalize", "not",
+    "null", "nullif", "numeric", "of", "offset", "old", "on", "only", "open", "or", "order", "out",
+    "outer", "over", "overlaps", "overlay", "parameter", "partition", "position", "precision",
+    "prepare", "primary", "procedure", "range", "reads", "real", "recursive", "ref", "references",
+    "referencing", "release", "result", "return", "returns", "revoke", "right", "rollback",
+    "rollup", "row", "rows", "savepoint", "scope", "scroll", "search", "second", "select",
+    "sensitive", "session_user", "set", "similar", "smallint", "some", "specific", "sql",
+    "sqlexception", "sqlstate", "sqlwarning", "start", "static", "submultiset", "substring", "sum",
+    "symmetric", "system", "system_user", "table", "tablesample", "then", "time", "timestamp",
+    "timezone_hour", "timezone_minute", "to", "trailing", "translate", "translation", "treat",
+    "trigger", "trim", "true", "union", "unique", "unknown", "unnest", "update", "upper", "user",
+    "using", "value", "values", "varchar", "varying", "when", "whenever", "where", "window", "with",
+    "within", "without", "year",
+];
+
+/// SQL dialects, which differ in how floating point results are typed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Ansi,
+    PostgreSql,
+    Sqlite,
+}
+
+/// When column names are wrapped in double quotes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quoting {
+    Always,
+    // only names that are not plain lowercase identifiers or are reserved words
+    WhenNeeded,
+    Never,
+}
+
+/// Value returned by the generated expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SqlOutput {
+    Label,
+    // probability of the given class
+    Probability(String),
+    // heap id of the leaf, as in `PreOrderTraversalIter`
+    LeafId,
+}
+
+#[derive(Debug, Clone)]
+pub struct SqlOptions {
+    pub dialect: Dialect,
+    pub quoting: Quoting,
+}
+
+impl Default for SqlOptions {
+    fn default() -> Self {
+        SqlOptions {
+            dialect: Dialect::Ansi,
+            quoting: Quoting::Always,
+        }
+    }
+}
+
+impl SqlOptions {
+    fn identifier(&self, name: &str) -> String {
+        let plain = name.chars().next().is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+            && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            && RESERVED.binary_search(&name).is_err();
+        match self.quoting {
+            Quoting::Never => name.to_string(),
+            Quoting::WhenNeeded if plain => name.to_string(),
+            _ => format!("\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+
+    fn number(&self, value: f64) -> String {
+        match self.dialect {
+            Dialect::Ansi => format!("CAST({:?} AS DOUBLE PRECISION)", value),
+            Dialect::PostgreSql => format!("{:?}::double precision", value),
+            Dialect::Sqlite => format!("{:?}", value),
+        }
+    }
+}
+
+fn string_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+struct SqlWriter<'a> {
+    output: &'a SqlOutput,
+    options: &'a SqlOptions,
+    sql: String,
+}
+
+impl<'a> SqlWriter<'a> {
+    // a NULL feature makes the condition unknown, falling into the
+    // ELSE branch like missing values do in `Tree::leaf`
+    fn write_node(&mut self, node: &Node<Decision>, id: usize, depth: usize) {
+        let indent = "  ".repeat(depth);
+        match (node.value.rule(), &node.left, &node.right) {
+            (Some(rule), Some(left), Some(right)) => {
+                let _ = writeln!(
+                    self.sql,
+                    "CASE WHEN {} > {:?} THEN",
+                    self.options.identifier(rule.dimension()),
+                    rule.cutoff()
+                );
+                self.sql.push_str(&indent);
+                self.sql.push_str("  ");
+                self.write_node(left, id << 1, depth + 1);
+                let _ = write!(self.sql, "\n{}ELSE ", indent);
+                self.write_node(right, (id << 1) + 1, depth + 1);
+                let _ = write!(self.sql, "\n{}END", indent);
+            }
+            _ => {
+                let value = match self.output {
+                    SqlOutput::Label => string_literal(node.value.prediction()),
+                    SqlOutput::Probability(class) => self.options.number(node.value.probability(class)),
+                    SqlOutput::LeafId => id.to_string(),
+                };
+                self.sql.push_str(&value);
+            }
+        }
+    }
+}
+
+impl Tree<Decision> {
+    /// Renders the tree as a SQL `CASE WHEN ... THEN ... END` expression
+    pub fn to_sql(&self, output: &SqlOutput, options: &SqlOptions) -> PolarsResult<String> {
+        let root = self
+            .root()
+            .ok_or_else(|| polars_err!(ComputeError: "cannot export an empty tree"))?;
+        if let SqlOutput::Probability(class) = output {
+            polars_ensure!(
+                self.classes().contains(class),
+                ComputeError: "the tree does not predict class `{}`", class
+            );
+        }
+        let mut writer = SqlWriter {
+            output,
+            options,
+            sql: String::new(),
+        };
+        writer.write_node(root, 1, 0);
+        Ok(writer.sql)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use rusqlite::Connection;
+    use std::collections::HashSet;
+
+    #[test]
+    fn sqlite_agrees_with_tree() {
+        let data = iris();
+        let features = ["sepal_length", "sepal_width", "petal_length", "petal_width"];
+        let tree = DTreeBuilder::new(HashSet::from(features), "variety")
+            .set_max_level(4)
+            .build(&data)
+            .unwrap();
+
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch("CREATE TABLE iris (sepal_length REAL, sepal_width REAL, petal_length REAL, petal_width REAL)")
+            .unwrap();
+        let mut insert = connection.prepare("INSERT INTO iris VALUES (?1, ?2, ?3, ?4)").unwrap();
+        let columns: Vec<Series> = features
+            .iter()
+            .map(|f| data.column(f).unwrap().cast(&DataType::Float64).unwrap())
+            .collect();
+        for i in 0..data.height() {
+            let row: Vec<f64> = columns.iter().map(|c| c.f64().unwrap().get(i).unwrap()).collect();
+            insert.execute(rusqlite::params_from_iter(row)).unwrap();
+        }
+
+        let options = SqlOptions {
+            dialect: Dialect::Sqlite,
+            quoting: Quoting::WhenNeeded,
+        };
+        let label = tree.to_sql(&SqlOutput::Label, &options).unwrap();
+        let probability = tree.to_sql(&SqlOutput::Probability("Versicolor".into()), &options).unwrap();
+        let query = format!("SELECT {}, {} FROM iris ORDER BY rowid", label, probability);
+        let mut statement = connection.prepare(&query).unwrap();
+        let rows: Vec<(String, f64)> = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+
+        let expected = tree.predict(&data).unwrap();
+        let probabilities = tree.predict_proba(&data).unwrap();
+        let versicolor = probabilities.column("Versicolor").unwrap().f64().unwrap();
+        for (i, (label, probability)) in rows.iter().enumerate() {
+            assert_eq!(Some(label.as_str()), expected.str().unwrap().get(i));
+            assert_eq!(Some(*probability), versicolor.get(i));
+        }
+        assert!(tree.to_sql(&SqlOutput::LeafId, &SqlOptions::default()).unwrap().contains("\"petal_"));
+        assert!(tree.to_sql(&SqlOutput::Probability("Rose".into()), &options).is_err());
+    }
+
+    #[test]
+    fn reserved_words_are_quoted() {
+        let mut data = iris();
+        data.rename("petal_length", "order").unwrap();
+        let tree = DTreeBuilder::new(HashSet::from(["order"]), "variety")
+            .set_max_level(2)
+            .build(&data)
+            .unwrap();
+        let options = SqlOptions {
+            dialect: Dialect::Sqlite,
+            quoting: Quoting::WhenNeeded,
+        };
+        let label = tree.to_sql(&SqlOutput::Label, &options).unwrap();
+        assert!(label.starts_with("CASE WHEN \"order\" > "));
+        assert_eq!(options.identifier("petal_width"), "petal_width");
+        assert_eq!(options.identifier("select"), "\"select\"");
+        assert!(RESERVED.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch("CREATE TABLE iris (\"order\" REAL); INSERT INTO iris VALUES (1.4);").unwrap();
+        let prediction: String = connection
+            .query_row(&format!("SELECT {} FROM iris", label), [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(prediction, "Setosa");
+    }
+}
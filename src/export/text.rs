@@ -0,0 +1,133 @@
+use crate::btree::{Node, Tree};
+use crate::Decision;
+use std::fmt::Write;
+
+/// Rendering options of `Tree::to_text`
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+    // decimal places of thresholds and confidences
+    pub decimals: usize,
+    // branches below this depth are collapsed into a single line
+    pub max_depth: Option<usize>,
+    // show the class counts next to each leaf
+    pub show_distribution: bool,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        TextOptions {
+            decimals: 2,
+            max_depth: None,
+            show_distribution: true,
+        }
+    }
+}
+
+struct TextWriter<'a> {
+    options: &'a TextOptions,
+    text: String,
+}
+
+impl<'a> TextWriter<'a> {
+    fn leaf(&self, decision: &Decision) -> String {
+        let mut line = format!(
+            "class: {} ({:.*})",
+            decision.prediction(),
+            self.options.decimals,
+            decision.confidence()
+        );
+        if self.options.show_distribution {
+            let counts: Vec<String> = decision
+                .distribution()
+                .iter()
+                .map(|(class, count)| format!("{}: {}", class, count))
+                .collect();
+            let _ = write!(line, " [{}]", counts.join(", "));
+        }
+        line
+    }
+
+    // writes the children of a node, `prefix` holds the vertical bars of the ancestors
+    fn write_children(&mut self, node: &Node<Decision>, prefix: &str, depth: usize) {
+        let (rule, left, right) = match (node.value.rule(), &node.left, &node.right) {
+            (Some(rule), Some(left), Some(right)) => (rule, left, right),
+            _ => {
+                let line = self.leaf(&node.value);
+                let _ = writeln!(self.text, "{}└── {}", prefix, line);
+                return;
+            }
+        };
+        if self.options.max_depth.is_some_and(|max| depth >= max) {
+            let _ = writeln!(
+                self.text,
+                "{}└── ... truncated branch of {} samples",
+                prefix,
+                node.value.samples()
+            );
+            return;
+        }
+        let decimals = self.options.decimals;
+        let branches = [
+            (format!("{} <= {:.*}", rule.dimension(), decimals, rule.cutoff()), right, false),
+            (format!("{} > {:.*}", rule.dimension(), decimals, rule.cutoff()), left, true),
+        ];
+        for (condition, child, last) in branches {
+            let (branch, bar) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+            let _ = writeln!(self.text, "{}{}{}", prefix, branch, condition);
+            self.write_children(child, &format!("{}{}", prefix, bar), depth + 1);
+        }
+    }
+}
+
+impl Tree<Decision> {
+    /// Renders the tree as indented text with box drawing branches, each
+    /// condition on its own line followed by its subtree
+    pub fn to_text(&self, options: &TextOptions) -> String {
+        let mut writer = TextWriter {
+            options,
+            text: String::new(),
+        };
+        if let Some(root) = self.root() {
+            if root.value.rule().is_none() {
+                let line = writer.leaf(&root.value);
+                let _ = writeln!(writer.text, "{}", line);
+            } else {
+                writer.write_children(root, "", 0);
+            }
+        }
+        writer.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use std::collections::HashSet;
+
+    #[test]
+    fn text_rendering() {
+        let data = iris();
+        let tree = DTreeBuilder::new(HashSet::from(["petal_length"]), "variety")
+            .set_max_level(3)
+            .build(&data)
+            .unwrap();
+        let text = tree.to_text(&TextOptions::default());
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "├── petal_length <= 2.45");
+        assert_eq!(lines[1], "│   └── class: Setosa (1.00) [Setosa: 50]");
+        assert_eq!(lines[2], "└── petal_length > 2.45");
+
+        let options = TextOptions {
+            decimals: 1,
+            max_depth: Some(1),
+            show_distribution: false,
+        };
+        let truncated = tree.to_text(&options);
+        assert_eq!(
+            truncated,
+            "├── petal_length <= 2.5\n│   └── class: Setosa (1.0)\n└── petal_length > 2.5\n    └── ... truncated branch of 100 samples\n"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::btree::Tree;
+use crate::Decision;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+// accumulates the weighted impurity decrease of each split by feature,
+// features that are never split on keep a decrease of zero
+fn impurity_decrease(tree: &Tree<Decision>, features: &[&str]) -> HashMap<String, f64> {
+    let mut decrease: HashMap<String, f64> = features.iter().map(|f| (f.to_string(), 0.0)).collect();
+    let mut items = tree.pre_order_iter().peekable();
+    // the root node holds the whole training set
+    let total = match items.peek() {
+        Some(root) => root.value.samples as f64,
+        None => return decrease,
+    };
+    for item in items {
+        if let Some(ref rule) = item.value.rule {
+            let share = item.value.samples as f64 / total;
+            *decrease.entry(rule.dimension.clone()).or_insert(0.0) +=
+                share * (item.value.impurity - rule.metric);
+        }
+    }
+    decrease
+}
+
+// rescales the importances so that they sum up to one
+fn normalise(mut importances: HashMap<String, f64>) -> HashMap<String, f64> {
+    let total: f64 = importances.values().sum();
+    if total > 0.0 {
+        for value in importances.values_mut() {
+            *value /= total;
+        }
+    }
+    importances
+}
+
+// creates a dataframe sorted by decreasing importance
+fn importance_frame(importances: HashMap<String, f64>) -> PolarsResult<DataFrame> {
+    let mut pairs: Vec<(String, f64)> = importances.into_iter().collect();
+    pairs.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let (features, values): (Vec<String>, Vec<f64>) = pairs.into_iter().unzip();
+    df!(
+        "feature" => features,
+        "importance" => values,
+    )
+}
+
+impl Tree<Decision> {
+    /// Mean decrease in impurity of each training feature, normalised to
+    /// sum up to one; features the tree never splits on are reported as zero
+    pub fn feature_importances(&self, features: &[&str]) -> PolarsResult<DataFrame> {
+        importance_frame(normalise(impurity_decrease(self, features)))
+    }
+}
+
+/// Averages the normalised impurity importances of an ensemble of trees
+pub fn ensemble_feature_importances(trees: &[Tree<Decision>], features: &[&str]) -> PolarsResult<DataFrame> {
+    let mut total: HashMap<String, f64> = HashMap::new();
+    for tree in trees {
+        for (feature, value) in normalise(impurity_decrease(tree, features)) {
+            *total.entry(feature).or_insert(0.0) += value;
+        }
+    }
+    // trees without any split do not contribute to the average
+    importance_frame(normalise(total))
+}
+
+// small deterministic generator used to shuffle columns (splitmix64)
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Fisher-Yates shuffle of the row indices
+    fn permutation(&mut self, size: usize) -> Vec<IdxSize> {
+        let mut indices: Vec<IdxSize> = (0..size as IdxSize).collect();
+        for i in (1..size).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            indices.swap(i, j);
+        }
+        indices
+    }
+}
+
+/// Permutation importance: the drop of `metric` when the values of each
+/// feature are shuffled, repeated `repeats` times starting from `seed`;
+/// `metric` receives the actual labels and the predictions
+pub fn permutation_importances<M>(
+    tree: &Tree<Decision>,
+    data: &DataFrame,
+    target: &str,
+    features: &[&str],
+    metric: M,
+    repeats: usize,
+    seed: u64,
+) -> PolarsResult<DataFrame>
+where
+    M: Fn(&Series, &Series) -> PolarsResult<f64>,
+{
+    polars_ensure!(repeats > 0, ComputeError: "at least one repetition is required");
+    let actual = data.column(target)?;
+    let baseline = metric(actual, &tree.predict(data)?)?;
+    let mut generator = SplitMix(seed);
+    let mut names: Vec<String> = Vec::new();
+    let mut means: Vec<f64> = Vec::new();
+    let mut stds: Vec<f64> = Vec::new();
+    for feature in features {
+        let column = data.column(feature)?;
+        let mut drops: Vec<f64> = Vec::with_capacity(repeats);
+        for _ in 0..repeats {
+            let shuffled = column.take_slice(&generator.permutation(data.height()))?;
+            let mut permuted = data.clone();
+            permuted.replace(feature, shuffled)?;
+            drops.push(baseline - metric(actual, &tree.predict(&permuted)?)?);
+        }
+        let mean = drops.iter().sum::<f64>() / repeats as f64;
+        let variance = drops.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / repeats as f64;
+        names.push(feature.to_string());
+        means.push(mean);
+        stds.push(variance.sqrt());
+    }
+    df!(
+        "feature" => names,
+        "importance_mean" => means,
+        "importance_std" => stds,
+    )?
+    .sort(["importance_mean"], SortMultipleOptions::default().with_order_descending(true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use std::collections::HashSet;
+
+    #[test]
+    fn importances_sum_to_one() {
+        let data = iris();
+        let features = ["sepal_length", "sepal_width", "petal_length", "petal_width"];
+        let tree = DTreeBuilder::new(HashSet::from(features), "variety")
+            .set_max_level(3)
+            .build(&data)
+            .unwrap();
+        let importances = tree.feature_importances(&features).unwrap();
+        // one row per training feature, unused ones included
+        assert_eq!(importances.height(), 4);
+        let values = importances.column("importance").unwrap().f64().unwrap();
+        assert!((values.sum().unwrap() - 1.0).abs() < 1e-9);
+        let first = importances.column("feature").unwrap().str().unwrap().get(0).unwrap();
+        assert!(first.starts_with("petal"));
+
+        let last = importances.column("importance").unwrap().f64().unwrap().get(3);
+        assert_eq!(last, Some(0.0));
+
+        let ensemble = ensemble_feature_importances(&[tree], &features).unwrap();
+        assert!(ensemble.equals(&importances));
+    }
+
+    #[test]
+    fn permutation_of_unused_feature() {
+        let data = iris();
+        let features = HashSet::from(["petal_length", "petal_width"]);
+        let tree = DTreeBuilder::new(features, "variety")
+            .set_max_level(2)
+            .build(&data)
+            .unwrap();
+        let columns = ["sepal_length", "petal_length", "petal_width"];
+        let importances =
+            permutation_importances(&tree, &data, "variety", &columns, crate::accuracy, 3, 42).unwrap();
+        assert_eq!(importances.height(), 3);
+        let names = importances.column("feature").unwrap().str().unwrap();
+        let means = importances.column("importance_mean").unwrap().f64().unwrap();
+        // the tree does not split on sepals, shuffling them has no effect
+        assert!(means.get(0).unwrap() > 0.0);
+        for (name, mean) in names.into_iter().zip(means) {
+            if name == Some("sepal_length") {
+                assert_eq!(mean, Some(0.0));
+            }
+        }
+    }
+}
@@ -1,23 +1,117 @@
+pub mod binary;
+pub mod boundary;
 pub mod btree;
+pub mod config;
+pub mod data;
+pub mod dependence;
+pub mod export;
+pub mod importance;
+pub mod model;
+mod plot;
+pub mod rules;
+pub mod score;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod shap;
 use polars::lazy::dsl::Expr;
 use polars::prelude::*;
 use polars::series::Series;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::Display;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rule {
     dimension: String,
     cutoff: f64,
     metric: f64,
 }
 
+impl Rule {
+    pub fn dimension(&self) -> &str {
+        &self.dimension
+    }
+
+    pub fn cutoff(&self) -> f64 {
+        self.cutoff
+    }
+
+    /// Weighted gini impurity of the two subsets created by the split
+    pub fn metric(&self) -> f64 {
+        self.metric
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Decision {
     rule: Option<Rule>,
     confidence: f64,
     prediction: String,
+    samples: usize,
+    weighted_samples: f64,
+    impurity: f64,
+    distribution: Vec<(String, usize)>,
+    depth: usize,
+}
+
+impl Decision {
+    pub fn rule(&self) -> Option<&Rule> {
+        self.rule.as_ref()
+    }
+
+    pub fn prediction(&self) -> &str {
+        &self.prediction
+    }
+
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    /// Number of training samples reaching the node
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Sum of the sample weights reaching the node, equal to the
+    /// sample count when the builder has no weight column
+    pub fn weighted_samples(&self) -> f64 {
+        self.weighted_samples
+    }
+
+    /// Gini impurity of the samples reaching the node
+    pub fn impurity(&self) -> f64 {
+        self.impurity
+    }
+
+    /// Count of each class among the samples reaching the node,
+    /// most frequent first
+    pub fn distribution(&self) -> &[(String, usize)] {
+        &self.distribution
+    }
+
+    /// Distance from the root, which has depth zero
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Frequency of a class among the samples reaching the node
+    pub fn probability(&self, class: &str) -> f64 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+        self.distribution
+            .iter()
+            .find(|(name, _)| name == class)
+            .map_or(0.0, |(_, count)| *count as f64 / self.samples as f64)
+    }
+
+    /// Lines of the `Display` label, which separates them with the DOT
+    /// escape `\n`, for `Tree::svg_dump_with`
+    pub fn label_lines(&self) -> Vec<String> {
+        self.to_string().split("\\n").map(String::from).collect()
+    }
 }
 
 impl Display for Decision{
@@ -34,37 +128,52 @@ impl Display for Decision{
 }
 
 #[derive(Debug)]
-pub struct DTreeBuilder<'a>{
+pub struct DTreeBuilder{
     max_level: usize,
     min_size: usize,
-    features: HashSet<& 'a str>,
-    target: & 'a str,
-    reuse_features: bool
+    features: HashSet<String>,
+    target: String,
+    weight: Option<String>,
+    reuse_features: bool,
+    // set when the builder comes from a configuration file
+    data: Option<String>,
 }
 
 // uses a struct to define trees constraints
-impl <'a>DTreeBuilder<'a> {
-    pub fn new(features: HashSet<& 'a str>, target : & 'a str) -> DTreeBuilder<'a>{
+impl DTreeBuilder {
+    pub fn new<I, S>(features: I, target: &str) -> DTreeBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
         DTreeBuilder{
             max_level: 3,
             min_size: 1,
-            features,
-            target,
-            reuse_features: true
+            features: features.into_iter().map(|f| f.into()).collect(),
+            target: target.to_string(),
+            weight: None,
+            reuse_features: true,
+            data: None,
         }
     }
 
-    pub fn set_max_level(mut self, max_level: usize) -> DTreeBuilder<'a>{
+    pub fn set_max_level(mut self, max_level: usize) -> DTreeBuilder{
         self.max_level = max_level;
         self
     }
 
-    pub fn set_min_size(mut self, min_size: usize) -> DTreeBuilder<'a>{
+    pub fn set_min_size(mut self, min_size: usize) -> DTreeBuilder{
         self.min_size = min_size;
         self
     }
 
-    pub fn set_reuse_features(mut self, reuse_features : bool) -> DTreeBuilder<'a>{
+    // numeric column summed into the weighted sample count of each node
+    pub fn set_weight(mut self, weight: &str) -> DTreeBuilder{
+        self.weight = Some(weight.to_string());
+        self
+    }
+
+    pub fn set_reuse_features(mut self, reuse_features : bool) -> DTreeBuilder{
         self.reuse_features = reuse_features;
         self
     }
@@ -75,19 +184,27 @@ impl <'a>DTreeBuilder<'a> {
         level: usize,
         features: & Option<HashSet<&str>>,
     ) -> PolarsResult<btree::Node<Decision>> {
-        println!("\nentering node level\n{1:->0$}{2:?}{1:-<0$}", 20, "\n", level);
-        println!("\ndata shape\n{1:->0$}{2:?}{1:-<0$}", 20, "\n", data.shape());
-        let prediction = predict_majority_dataframe(data, self.target)?;
-        println!("\ndecision\n{1:->0$}{2:?}{1:-<0$}", 20, "\n", &prediction);
+        let mut prediction = predict_majority_dataframe(data, &self.target)?;
+        prediction.depth = level - 1;
+        if let Some(weight) = &self.weight {
+            prediction.weighted_samples = data
+                .column(weight)?
+                .cast(&DataType::Float64)?
+                .f64()?
+                .sum()
+                .unwrap_or(0.0);
+        }
         let confidence = prediction.confidence;
         let mut node = btree::Node::new(prediction);
-        let current_features = features.clone().unwrap_or(self.features.clone());
+        let current_features = features
+            .clone()
+            .unwrap_or_else(|| self.features.iter().map(|f| f.as_str()).collect());
         // check stop conditions
         if (!current_features.is_empty()) && // exhausted features
             (confidence < 1.0) && // all elements belong to one category
             (data.shape().0 > self.min_size) && // size is below minimum threshold
             (level <= self.max_level){ // maximum depth reached
-                let rule = evaluate_best_split(data, & current_features, self.target)?;
+                let rule = evaluate_best_split(data, & current_features, &self.target)?;
                 let higher: DataFrame = data
                     .clone()
                     .lazy()
@@ -104,11 +221,6 @@ impl <'a>DTreeBuilder<'a> {
                         let mut reduced_features =
                             feats.clone();
                         reduced_features.remove(rule.dimension.as_str());
-                        let feats_vec: Vec<String> = reduced_features
-                            .iter()
-                            .map(|s| s.to_string())
-                            .collect();
-                        print!("features {}",feats_vec.join(","));
                         Some(reduced_features)
                     }
                 };
@@ -127,15 +239,71 @@ impl <'a>DTreeBuilder<'a> {
         data: & DataFrame,
     ) -> PolarsResult<btree::Tree<Decision>> {
         let current_features = if !self.reuse_features {
-            let feats = self.features.clone();
+            let feats = self.features.iter().map(|f| f.as_str()).collect();
             Some(feats)
         }else{
             None
         };
-        println!("{1:->0$}{2:?}{1:-<0$}", 20, "\n", self);
         let root = self.build_node(data, 1, & current_features)?;
         Ok(btree::Tree::from_node(root))
     }
+
+    // builds a tree from a training configuration
+    pub fn from_config(config: &config::TrainingConfig) -> PolarsResult<DTreeBuilder> {
+        polars_ensure!(
+            config.criterion == config::GINI,
+            ComputeError: "criterion `{}` is not supported, use `{}`", config.criterion, config::GINI
+        );
+        let mut builder = DTreeBuilder::new(&config.features, &config.target)
+            .set_max_level(config.max_level)
+            .set_min_size(config.min_size)
+            .set_reuse_features(config.reuse_features);
+        builder.weight = config.weight.clone();
+        builder.data = config.data.clone();
+        Ok(builder)
+    }
+
+    // the configuration reproducing this builder, features sorted
+    pub fn config(&self) -> config::TrainingConfig {
+        let mut features: Vec<String> = self.features.iter().cloned().collect();
+        features.sort();
+        config::TrainingConfig {
+            data: self.data.clone(),
+            target: self.target.clone(),
+            features,
+            criterion: config::GINI.to_string(),
+            max_level: self.max_level,
+            min_size: self.min_size,
+            reuse_features: self.reuse_features,
+            weight: self.weight.clone(),
+        }
+    }
+
+    pub fn parameters(&self) -> model::Hyperparameters {
+        model::Hyperparameters {
+            max_level: self.max_level,
+            min_size: self.min_size,
+            reuse_features: self.reuse_features,
+            weight: self.weight.clone(),
+        }
+    }
+
+    // builds the tree keeping track of the training schema
+    pub fn fit(
+        &self,
+        data: & DataFrame,
+    ) -> PolarsResult<model::Model> {
+        let tree = self.build(data)?;
+        let config = self.config();
+        Ok(model::Model {
+            classes: tree.classes(),
+            tree,
+            target: self.target.clone(),
+            features: config.features.clone(),
+            parameters: self.parameters(),
+            config: Some(config),
+        })
+    }
 }
 
 // Gini impurity metric
@@ -160,13 +328,22 @@ pub fn predict_majority_dataframe<'a>(data: &'a DataFrame, target: &str) -> Pola
 
     let total = labels.len() as f64;
 
+    // node impurity is kept to evaluate the decrease granted by a split
+    let impurity = estimate_gini(data, target)?;
+
     // count all categories and sort them
     let result_count = labels.value_counts()?;
-    println!("\ncategory count\n{1:->0$}{2:?}{1:-<0$}", 20, "\n", result_count);
+
+    // keep the count of every category
+    let names = result_count.column(target)?.categorical()?.iter_str();
+    let counts = result_count.column("counts")?.u32()?.into_iter();
+    let distribution: Vec<(String, usize)> = names
+        .zip(counts)
+        .filter_map(|(name, count)| Some((name?.to_string(), count? as usize)))
+        .collect();
 
     // get the most frequent category
     let result_cat = result_count.head(Some(1));
-    println!("\nfirst selected category\n{1:->0$}{2:?}{1:-<0$}", 20, "\n", result_cat);
 
     // transform the series into a categorical vector
     let actual_cat = result_cat
@@ -179,7 +356,6 @@ pub fn predict_majority_dataframe<'a>(data: &'a DataFrame, target: &str) -> Pola
         .flatten()
         .map(|name| (*name).into())
         .collect();
-    println!("\nreturned string\n{1:->0$}{2:?}{1:-<0$}", 20, "\n", string_cat);
 
     let probability: Vec<f64>= result_cat
         .column("counts")?
@@ -199,7 +375,12 @@ pub fn predict_majority_dataframe<'a>(data: &'a DataFrame, target: &str) -> Pola
             confidence: probability
                 .get(0)
                 .unwrap()
-                .to_owned()
+                .to_owned(),
+            samples: labels.len(),
+            weighted_samples: total,
+            impurity,
+            distribution,
+            depth: 0,
         }
     );
 }
@@ -271,10 +452,6 @@ pub fn evaluate_best_split<'a>(
         to_supertypes: true,
     };
     let concat_metrics: DataFrame = concat(metrics?, concat_rules)?.collect()?;
-    println!(
-        "\nconcat_metrics\n{1:->0$}{2:?}{1:-<0$}\n",
-        20, "\n", concat_metrics
-    );
 
     // search for the best split
     let expr: Expr = col("metrics").lt_eq(col("metrics").min());
@@ -284,10 +461,6 @@ pub fn evaluate_best_split<'a>(
         .filter(expr)
         .select([col("feature"), col("split"), col("metrics")])
         .collect()?;
-    println!(
-        "\nbest_split\n{1:->0$}{2:?}{1:-<0$}\n",
-        20, "\n", best_split
-    );
 
     let chosen_features: Vec<String> = best_split
         .column("feature")?
@@ -310,15 +483,246 @@ pub fn evaluate_best_split<'a>(
     })
 }
 
-pub fn print_tree(tree: & btree::Tree<Decision>){
-    for item in tree.pre_order_iter(){
-        for _ in 0..item.level{
-            print!(" | ");
+impl btree::Tree<Decision> {
+    // walks down the tree for a single observation, calling `visit` with the
+    // heap id of every node on the way, and returns the decision reached
+    fn walk<F, V>(&self, value: F, mut visit: V) -> Option<&Decision>
+    where
+        F: Fn(&str) -> Option<f64>,
+        V: FnMut(usize),
+    {
+        let mut node = self.root()?;
+        let mut id = 1;
+        visit(id);
+        while let Some(ref rule) = node.value.rule {
+            let (next, next_id) = if value(&rule.dimension).is_some_and(|v| v > rule.cutoff) {
+                (&node.left, id << 1)
+            } else {
+                (&node.right, (id << 1) + 1)
+            };
+            match next {
+                Some(child) => {
+                    node = child;
+                    id = next_id;
+                    visit(id);
+                }
+                None => break,
+            }
         }
-        println!("{} {:?}",item.id, item.value);
+        Some(&node.value)
+    }
+
+    /// Walks down the tree for a single observation; `value` returns the
+    /// feature value by name, missing values follow the `<=` branch
+    pub fn leaf<F>(&self, value: F) -> Option<&Decision>
+    where
+        F: Fn(&str) -> Option<f64>,
+    {
+        self.walk(value, |_| {})
+    }
+
+    /// Heap ids of the nodes visited by `leaf`, from the root down to the leaf
+    pub fn decision_path<F>(&self, value: F) -> Vec<usize>
+    where
+        F: Fn(&str) -> Option<f64>,
+    {
+        let mut path = Vec::new();
+        self.walk(value, |id| path.push(id));
+        path
+    }
+
+    /// Heap ids of the nodes visited by a row of the dataframe
+    pub fn row_path(&self, data: &DataFrame, row: usize) -> PolarsResult<Vec<usize>> {
+        polars_ensure!(
+            row < data.height(),
+            OutOfBounds: "row {} is out of range for {} rows", row, data.height()
+        );
+        let columns = self.feature_columns(&data.slice(row as i64, 1))?;
+        Ok(self.decision_path(|name| columns.get(name).and_then(|values| values[0])))
+    }
+
+    // collects the columns used by the splits as floating point vectors
+    pub(crate) fn feature_columns(&self, data: &DataFrame) -> PolarsResult<HashMap<String, Vec<Option<f64>>>> {
+        let mut columns = HashMap::new();
+        for item in self.pre_order_iter() {
+            if let Some(ref rule) = item.value.rule {
+                if !columns.contains_key(&rule.dimension) {
+                    let values: Vec<Option<f64>> = data
+                        .column(&rule.dimension)?
+                        .cast(&DataType::Float64)?
+                        .f64()?
+                        .into_iter()
+                        .collect();
+                    columns.insert(rule.dimension.clone(), values);
+                }
+            }
+        }
+        Ok(columns)
+    }
+
+    /// Class labels seen at training time, in lexical order
+    pub fn classes(&self) -> Vec<String> {
+        let mut classes: Vec<String> = self
+            .root()
+            .map(|root| root.value.distribution.iter().map(|(name, _)| name.clone()).collect())
+            .unwrap_or_default();
+        classes.sort();
+        classes
+    }
+
+    /// Predicts the class of each row of the dataframe
+    pub fn predict(&self, data: &DataFrame) -> PolarsResult<Series> {
+        let columns = self.feature_columns(data)?;
+        let predictions: Vec<Option<String>> = (0..data.height())
+            .map(|row| {
+                self.leaf(|feature| columns.get(feature).and_then(|c| c[row]))
+                    .map(|decision| decision.prediction.clone())
+            })
+            .collect();
+        Ok(Series::new("prediction", predictions))
+    }
+
+    /// Probability of each class for every row of the dataframe, one column per class
+    pub fn predict_proba(&self, data: &DataFrame) -> PolarsResult<DataFrame> {
+        let columns = self.feature_columns(data)?;
+        let leaves: Vec<Option<&Decision>> = (0..data.height())
+            .map(|row| self.leaf(|feature| columns.get(feature).and_then(|c| c[row])))
+            .collect();
+        let probabilities: Vec<Series> = self
+            .classes()
+            .iter()
+            .map(|class| {
+                let values: Vec<Option<f64>> = leaves
+                    .iter()
+                    .map(|leaf| leaf.map(|decision| decision.probability(class)))
+                    .collect();
+                Series::new(class, values)
+            })
+            .collect();
+        DataFrame::new(probabilities)
     }
 }
 
+// share of predictions matching the actual labels
+pub fn accuracy(actual: &Series, predicted: &Series) -> PolarsResult<f64> {
+    polars_ensure!(
+        actual.len() == predicted.len(),
+        ShapeMismatch: "cannot compare {} labels with {} predictions", actual.len(), predicted.len()
+    );
+    if actual.is_empty() {
+        return Ok(0.0);
+    }
+    let actual = actual.cast(&DataType::String)?;
+    let predicted = predicted.cast(&DataType::String)?;
+    let hits = actual
+        .str()?
+        .into_iter()
+        .zip(predicted.str()?)
+        .filter(|(a, p)| a.is_some() && a == p)
+        .count();
+    Ok(hits as f64 / actual.len() as f64)
+}
+
+// precision, recall, f1 score and support of each class, sorted by class
+pub fn classification_report(actual: &Series, predicted: &Series) -> PolarsResult<DataFrame> {
+    polars_ensure!(
+        actual.len() == predicted.len(),
+        ShapeMismatch: "cannot compare {} labels with {} predictions", actual.len(), predicted.len()
+    );
+    let actual = actual.cast(&DataType::String)?;
+    let predicted = predicted.cast(&DataType::String)?;
+    // per class: (true positives, predicted count, actual count)
+    let mut counts: std::collections::BTreeMap<&str, (usize, usize, usize)> = Default::default();
+    for (a, p) in actual.str()?.into_iter().zip(predicted.str()?) {
+        if let Some(a) = a {
+            counts.entry(a).or_default().2 += 1;
+        }
+        if let Some(p) = p {
+            counts.entry(p).or_default().1 += 1;
+        }
+        if let (Some(a), Some(p)) = (a, p) {
+            if a == p {
+                counts.entry(a).or_default().0 += 1;
+            }
+        }
+    }
+    let ratio = |n: usize, d: usize| if d == 0 { 0.0 } else { n as f64 / d as f64 };
+    let precision: Vec<f64> = counts.values().map(|&(tp, p, _)| ratio(tp, p)).collect();
+    let recall: Vec<f64> = counts.values().map(|&(tp, _, a)| ratio(tp, a)).collect();
+    let f1: Vec<f64> = precision
+        .iter()
+        .zip(&recall)
+        .map(|(p, r)| if p + r == 0.0 { 0.0 } else { 2.0 * p * r / (p + r) })
+        .collect();
+    df!(
+        "class" => counts.keys().copied().collect::<Vec<_>>(),
+        "precision" => precision,
+        "recall" => recall,
+        "f1" => f1,
+        "support" => counts.values().map(|&(_, _, a)| a as u64).collect::<Vec<_>>(),
+    )
+}
+
+pub fn print_tree(tree: & btree::Tree<Decision>){
+    print!("{}", tree.to_text(&export::text::TextOptions::default()));
+}
+
 #[cfg(test)]
 mod test {
+    use super::*;
+
+    // loads the iris dataset with a categorical target
+    pub(crate) fn iris() -> DataFrame {
+        let mut data = CsvReader::from_path("src/iris.csv")
+            .unwrap()
+            .has_header(true)
+            .finish()
+            .unwrap();
+        data.try_apply("variety", |s| {
+            s.cast(&DataType::Categorical(None, CategoricalOrdering::Lexical))
+        })
+        .unwrap();
+        data
+    }
+
+    #[test]
+    fn nodes_keep_statistics() {
+        let mut data = iris();
+        data.with_column(Series::new("weight", vec![2.0; data.height()])).unwrap();
+        let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
+        let tree = DTreeBuilder::new(features, "variety")
+            .set_max_level(3)
+            .set_weight("weight")
+            .build(&data)
+            .unwrap();
+        let root = tree.root().unwrap();
+        assert_eq!(root.value.samples(), 150);
+        assert_eq!(root.value.weighted_samples(), 300.0);
+        assert_eq!(root.value.depth(), 0);
+        assert!((root.value.impurity() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(root.value.distribution().len(), 3);
+        for item in tree.pre_order_iter() {
+            let decision = item.value;
+            assert_eq!(decision.depth(), item.level - 1);
+            let counted: usize = decision.distribution().iter().map(|(_, count)| count).sum();
+            assert_eq!(counted, decision.samples());
+        }
+        let left = root.left.as_ref().unwrap();
+        let right = root.right.as_ref().unwrap();
+        assert_eq!(left.value.samples() + right.value.samples(), root.value.samples());
+    }
+
+    #[test]
+    fn report_per_class() {
+        let actual = Series::new("actual", ["a", "a", "b", "b"]);
+        let predicted = Series::new("predicted", ["a", "b", "b", "b"]);
+        let report = classification_report(&actual, &predicted).unwrap();
+        let precision = report.column("precision").unwrap().f64().unwrap();
+        let recall = report.column("recall").unwrap().f64().unwrap();
+        assert_eq!(precision.get(0), Some(1.0));
+        assert_eq!(recall.get(0), Some(0.5));
+        assert!((precision.get(1).unwrap() - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(recall.get(1), Some(1.0));
+        assert_eq!(accuracy(&actual, &predicted).unwrap(), 0.75);
+    }
 }
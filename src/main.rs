@@ -1,67 +1,378 @@
-use std::fs;
-use decision::{evaluate_best_split, DTreeBuilder};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use decision::export::dot::DotOptions;
+use decision::export::sql::{SqlOptions, SqlOutput};
+use decision::export::text::TextOptions;
+use decision::model::{Hyperparameters, Model};
+use decision::score::{score_stream, ScoreOptions};
+#[cfg(feature = "serve")]
+use decision::serve::{PredictionServer, MAX_BODY};
+use decision::config::{TrainingConfig, GINI};
+use decision::data::{numeric_columns, prepare_training_data, DataLoader, Format};
+use decision::{accuracy, classification_report, DTreeBuilder, Decision};
 use polars::prelude::*;
-use std::collections::HashSet;
+use std::fs;
+use std::io::{BufWriter, Write};
+#[cfg(feature = "serve")]
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-fn main() -> polars::prelude::PolarsResult<()> {
-    let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
-    let target = "variety";
+/// Train decision trees and use them from the command line
+#[derive(Parser)]
+#[command(name = "decision", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-    let data = load_data("iris.csv", target)?;
+#[derive(Subcommand)]
+enum Command {
+    /// Train a tree and save it as a JSON model
+    Train(TrainArgs),
+    /// Predict the class of each row of a CSV file
+    Predict(PredictArgs),
+    /// Report accuracy and per class metrics on labelled data
+    Evaluate(EvaluateArgs),
+    /// Render a saved model as a graph, an image, text or a SQL expression
+    Export(ExportArgs),
+    /// Answer prediction requests over HTTP
+    #[cfg(feature = "serve")]
+    Serve(ServeArgs),
+    /// Score records streamed on standard input to standard output
+    Score(ScoreArgs),
+}
 
-    let rule = evaluate_best_split(& data, & features, target)?;
+#[derive(Clone, Copy, ValueEnum)]
+enum DataFormat {
+    Csv,
+    Parquet,
+    Ipc,
+    Json,
+    Ndjson,
+}
 
-    println!(
-        "\nrule\n{1:->0$}{2:?}{1:-<0$}\n",
-        20, "\n", rule
-    );
+#[derive(Args)]
+struct DataArgs {
+    /// Data file, its format is guessed from the extension
+    #[arg(long)]
+    data: Option<PathBuf>,
+    /// Format of the data file, overriding the extension
+    #[arg(long, value_enum)]
+    data_format: Option<DataFormat>,
+    /// Field separator of CSV files
+    #[arg(long, default_value_t = ',')]
+    separator: char,
+    /// CSV files have no header line
+    #[arg(long)]
+    no_header: bool,
+    /// Strings read as missing values in CSV files
+    #[arg(long, value_delimiter = ',')]
+    null_values: Vec<String>,
+}
 
-    let builder = DTreeBuilder::new(features.clone(), target)
-        .set_max_level(7);
+#[derive(Args)]
+struct TrainArgs {
+    /// TOML or YAML training configuration, replacing the options below
+    #[cfg(feature = "config")]
+    #[arg(long, conflicts_with_all = ["target", "features", "max_level", "min_size", "weight", "no_reuse_features"])]
+    config: Option<PathBuf>,
+    /// Overrides the data file of the configuration
+    #[command(flatten)]
+    data: DataArgs,
+    /// Column holding the class labels
+    #[cfg_attr(feature = "config", arg(long, required_unless_present = "config"))]
+    #[cfg_attr(not(feature = "config"), arg(long, required = true))]
+    target: Option<String>,
+    /// Columns used for splitting, all numeric columns when omitted
+    #[arg(long, value_delimiter = ',', num_args = 1..)]
+    features: Vec<String>,
+    #[arg(long, default_value_t = Hyperparameters::default().max_level)]
+    max_level: usize,
+    #[arg(long, default_value_t = Hyperparameters::default().min_size)]
+    min_size: usize,
+    /// Numeric column weighting each sample
+    #[arg(long)]
+    weight: Option<String>,
+    /// Split at most once on each feature along a path
+    #[arg(long)]
+    no_reuse_features: bool,
+    /// Where the JSON model is written
+    #[arg(long)]
+    out: PathBuf,
+}
 
-    let tree = builder.build(& data)?;
+#[derive(Args)]
+struct PredictArgs {
+    #[arg(long)]
+    model: PathBuf,
+    #[command(flatten)]
+    data: DataArgs,
+    /// Also write the probability of each class
+    #[arg(long)]
+    probabilities: bool,
+    /// CSV file for the predictions, standard output when omitted
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
 
-    decision::print_tree(& tree);
+#[derive(Args)]
+struct EvaluateArgs {
+    #[arg(long)]
+    model: PathBuf,
+    /// Must contain the target column of the model
+    #[command(flatten)]
+    data: DataArgs,
+}
 
-    let dump = & tree.dot_dump("yes","no");
-    fs::write("./iris1.dot", dump).expect("Unable to write file iris1.dot");
-    println!("{}",dump);
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Dot,
+    Svg,
+    Mermaid,
+    Plantuml,
+    Html,
+    Text,
+    Sql,
+}
 
-    let builder = DTreeBuilder::new(features.clone(), target)
-        .set_reuse_features(false)
-        .set_max_level(7);
+#[derive(Args)]
+struct ExportArgs {
+    #[arg(long)]
+    model: PathBuf,
+    #[arg(long, value_enum)]
+    format: ExportFormat,
+    /// Highlight the decision path of this row of the data file (dot only)
+    #[arg(long, requires = "data")]
+    highlight_row: Option<usize>,
+    #[command(flatten)]
+    data: DataArgs,
+    /// Output file, standard output when omitted
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
 
-    let tree = builder.build(& data)?;
+#[cfg(feature = "serve")]
+#[derive(Args)]
+struct ServeArgs {
+    #[arg(long)]
+    model: PathBuf,
+    /// Address to listen on, only the local machine by default
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+    /// Largest accepted request body in bytes
+    #[arg(long, default_value_t = MAX_BODY)]
+    max_body: usize,
+}
 
-    decision::print_tree(& tree);
+#[derive(Clone, Copy, ValueEnum)]
+enum StreamFormat {
+    Csv,
+    Ndjson,
+}
 
-    let dump = & tree.dot_dump("yes","no");
-    fs::write("./iris2.dot", dump).expect("Unable to write file iris1.dot");
-    println!("{}",dump);
+#[derive(Args)]
+struct ScoreArgs {
+    #[arg(long)]
+    model: PathBuf,
+    /// Format of the records read and written
+    #[arg(long, value_enum, default_value = "ndjson")]
+    format: StreamFormat,
+    /// Records scored together
+    #[arg(long, default_value_t = 1000)]
+    chunk_size: usize,
+    /// Column copied from each record to its prediction
+    #[arg(long)]
+    id: Option<String>,
+    /// Also write the probability of each class
+    #[arg(long)]
+    probabilities: bool,
+}
 
-    let builder = DTreeBuilder::new(features.clone(), target)
-        .set_max_level(3);
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Train(args) => train(args),
+        Command::Predict(args) => predict(args),
+        Command::Evaluate(args) => evaluate(args),
+        Command::Export(args) => export(args),
+        #[cfg(feature = "serve")]
+        Command::Serve(args) => serve(args),
+        Command::Score(args) => score(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn train(args: TrainArgs) -> PolarsResult<()> {
+    // configuration files are only read with the `config` feature
+    #[cfg(feature = "config")]
+    let loaded = args.config.as_ref().map(TrainingConfig::load).transpose()?;
+    #[cfg(not(feature = "config"))]
+    let loaded = None;
+    let mut config = match loaded {
+        Some(config) => config,
+        None => TrainingConfig {
+            data: None,
+            target: args.target.clone().unwrap_or_default(),
+            features: args.features.clone(),
+            criterion: GINI.to_string(),
+            max_level: args.max_level,
+            min_size: args.min_size,
+            reuse_features: !args.no_reuse_features,
+            weight: args.weight.clone(),
+        },
+    };
+    let mut data_args = args.data;
+    match &data_args.data {
+        Some(path) => config.data = Some(path.display().to_string()),
+        None => data_args.data = config.data.as_ref().map(PathBuf::from),
+    }
+    let mut data = load_data(&data_args)?;
+    if config.features.is_empty() {
+        let mut excluded = vec![config.target.as_str()];
+        excluded.extend(config.weight.as_deref());
+        config.features = numeric_columns(&data, &excluded);
+        polars_ensure!(!config.features.is_empty(), ComputeError: "no numeric feature columns in the data");
+    }
+    let features: Vec<&str> = config.features.iter().map(|f| f.as_str()).collect();
+    prepare_training_data(&mut data, &config.target, &features)?;
 
-    let tree = builder.build(& data)?;
+    let model = DTreeBuilder::from_config(&config)?.fit(&data)?;
+    model.save(&args.out)
+}
 
-    decision::print_tree(& tree);
+fn predict(args: PredictArgs) -> PolarsResult<()> {
+    let model = load_model(&args.model)?;
+    let data = load_data(&args.data)?;
+    let mut predictions = DataFrame::new(vec![model.predict(&data)?])?;
+    if args.probabilities {
+        predictions = predictions.hstack(model.tree.predict_proba(&data)?.get_columns())?;
+    }
+    let mut buffer: Vec<u8> = Vec::new();
+    CsvWriter::new(&mut buffer).include_header(true).finish(&mut predictions)?;
+    write_output(args.out.as_deref(), &buffer)
+}
+
+fn evaluate(args: EvaluateArgs) -> PolarsResult<()> {
+    let model = load_model(&args.model)?;
+    let mut data = load_data(&args.data)?;
+    let features: Vec<&str> = model.features.iter().map(|f| f.as_str()).collect();
+    prepare_training_data(&mut data, &model.target, &features)?;
+    let actual = data.column(&model.target)?;
+    let predicted = model.predict(&data)?;
+    let report = classification_report(actual, &predicted)?;
+
+    let mut text = format!("samples: {}\naccuracy: {:.4}\n\n", data.height(), accuracy(actual, &predicted)?);
+    text.push_str(&format!(
+        "{:<20} {:>9} {:>9} {:>9} {:>9}\n",
+        "class", "precision", "recall", "f1", "support"
+    ));
+    let classes = report.column("class")?.str()?;
+    let precision = report.column("precision")?.f64()?;
+    let recall = report.column("recall")?.f64()?;
+    let f1 = report.column("f1")?.f64()?;
+    let support = report.column("support")?.u64()?;
+    for i in 0..report.height() {
+        text.push_str(&format!(
+            "{:<20} {:>9.4} {:>9.4} {:>9.4} {:>9}\n",
+            classes.get(i).unwrap_or_default(),
+            precision.get(i).unwrap_or_default(),
+            recall.get(i).unwrap_or_default(),
+            f1.get(i).unwrap_or_default(),
+            support.get(i).unwrap_or_default()
+        ));
+    }
+    write_output(None, text.as_bytes())
+}
 
-    let dump = & tree.dot_dump("yes","no");
-    fs::write("./iris3.dot", dump).expect("Unable to write file iris3.dot");
-    println!("{}",dump);
+fn export(args: ExportArgs) -> PolarsResult<()> {
+    let model = load_model(&args.model)?;
+    let rendered = match args.format {
+        ExportFormat::Dot => {
+            let mut options = DotOptions::default();
+            if let Some(row) = args.highlight_row {
+                let data = load_data(&args.data)?;
+                options.highlight = model.tree.row_path(&data, row)?.into_iter().collect();
+            }
+            model.tree.to_dot(&options)
+        }
+        ExportFormat::Svg => model.tree.svg_dump_with("yes", "no", Decision::label_lines),
+        ExportFormat::Mermaid => model.tree.mermaid_dump("yes", "no"),
+        ExportFormat::Plantuml => model.tree.plantuml_dump("yes", "no"),
+        ExportFormat::Html => model.tree.to_html(&format!("Decision tree predicting {}", model.target))?,
+        ExportFormat::Text => model.tree.to_text(&TextOptions::default()),
+        ExportFormat::Sql => model.tree.to_sql(&SqlOutput::Label, &SqlOptions::default())? + "\n",
+    };
+    write_output(args.out.as_deref(), rendered.as_bytes())
+}
+
+#[cfg(feature = "serve")]
+fn serve(args: ServeArgs) -> PolarsResult<()> {
+    let model = load_model(&args.model)?;
+    let address = format!("{}:{}", args.host, args.port);
+    let listener = TcpListener::bind(&address)
+        .map_err(|e| polars_err!(ComputeError: "cannot listen on {}: {}", address, e))?;
+    eprintln!("serving {} on http://{}", args.model.display(), address);
+    PredictionServer::new(model)
+        .set_max_body(args.max_body)
+        .run(listener)
+}
+
+fn score(args: ScoreArgs) -> PolarsResult<()> {
+    let model = load_model(&args.model)?;
+    let options = ScoreOptions {
+        format: match args.format {
+            StreamFormat::Csv => Format::Csv,
+            StreamFormat::Ndjson => Format::NdJson,
+        },
+        chunk_size: args.chunk_size,
+        id: args.id,
+        probabilities: args.probabilities,
+    };
+    let mut output = BufWriter::new(std::io::stdout().lock());
+    score_stream(&model, std::io::stdin().lock(), &mut output, &options)?;
     Ok(())
 }
 
-fn load_data(path: &str, target: &str) -> PolarsResult<DataFrame> {
-    // read data file
-    let mut data = CsvReader::from_path(path)?.has_header(true).finish()?;
-    println!("\ndata\n{1:->0$}{2:?}{1:-<0$}\n", 20, "\n", data);
+fn load_model(path: &Path) -> PolarsResult<Model> {
+    Model::load(path).map_err(|e| polars_err!(ComputeError: "cannot load model {}: {}", path.display(), e))
+}
 
-    // set target column as categorical
-    data.try_apply(target, |s| {
-        s.cast(&DataType::Categorical(None, CategoricalOrdering::Lexical))
-    })?;
-    println!("\ndata\n{1:->0$}{2:?}{1:-<0$}\n", 20, "\n", data);
+fn write_output(path: Option<&Path>, content: &[u8]) -> PolarsResult<()> {
+    match path {
+        Some(path) => fs::write(path, content)
+            .map_err(|e| polars_err!(ComputeError: "cannot write {}: {}", path.display(), e)),
+        None => std::io::stdout()
+            .write_all(content)
+            .map_err(|e| polars_err!(ComputeError: "cannot write to standard output: {}", e)),
+    }
+}
 
-    Ok(data)
+fn load_data(args: &DataArgs) -> PolarsResult<DataFrame> {
+    let path = args
+        .data
+        .as_ref()
+        .ok_or_else(|| polars_err!(ComputeError: "no data file given, use --data"))?;
+    polars_ensure!(args.separator.is_ascii(), ComputeError: "the separator must be an ASCII character");
+    let mut loader = DataLoader::new(path)
+        .set_separator(args.separator as u8)
+        .set_has_header(!args.no_header)
+        .set_null_values(args.null_values.clone());
+    if let Some(format) = args.data_format {
+        loader = loader.set_format(match format {
+            DataFormat::Csv => Format::Csv,
+            DataFormat::Parquet => Format::Parquet,
+            DataFormat::Ipc => Format::Ipc,
+            DataFormat::Json => Format::Json,
+            DataFormat::Ndjson => Format::NdJson,
+        });
+    }
+    loader.load()
 }
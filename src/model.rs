@@ -0,0 +1,155 @@
+use crate::btree::Tree;
+use crate::config::TrainingConfig;
+use crate::{DTreeBuilder, Decision};
+use polars::prelude::*;
+
+/// Stopping rules used by `DTreeBuilder` to grow a tree
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hyperparameters {
+    pub max_level: usize,
+    pub min_size: usize,
+    pub reuse_features: bool,
+    pub weight: Option<String>,
+}
+
+// the defaults are those of `DTreeBuilder::new`
+impl Default for Hyperparameters {
+    fn default() -> Self {
+        DTreeBuilder::new(Vec::<String>::new(), "").parameters()
+    }
+}
+
+/// A trained tree together with the schema of its training data
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Model {
+    pub tree: Tree<Decision>,
+    pub target: String,
+    pub features: Vec<String>,
+    pub classes: Vec<String>,
+    pub parameters: Hyperparameters,
+    // resolved configuration of the training run, missing in older files
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub config: Option<TrainingConfig>,
+}
+
+impl Model {
+    /// Predicts the class of each row of the dataframe
+    pub fn predict(&self, data: &DataFrame) -> PolarsResult<Series> {
+        self.tree.predict(data)
+    }
+
+    /// Impurity-based importance of each training feature
+    pub fn feature_importances(&self) -> PolarsResult<DataFrame> {
+        let features: Vec<&str> = self.features.iter().map(|f| f.as_str()).collect();
+        self.tree.feature_importances(&features)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod json {
+    use super::Model;
+    use polars::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use std::fs;
+    use std::path::Path;
+
+    const FORMAT: &str = "decision-tree";
+    const VERSION: u32 = 1;
+
+    // file layout: a format marker and version followed by the model
+    #[derive(Serialize)]
+    struct Envelope<'a> {
+        format: &'a str,
+        version: u32,
+        model: &'a Model,
+    }
+
+    #[derive(Deserialize)]
+    struct Header {
+        format: Option<String>,
+        version: Option<u32>,
+    }
+
+    #[derive(Deserialize)]
+    struct Content {
+        model: Model,
+    }
+
+    fn json_error(error: serde_json::Error) -> PolarsError {
+        polars_err!(ComputeError: "invalid model file: {}", error)
+    }
+
+    impl Model {
+        /// Serialises the model in the versioned JSON format
+        pub fn to_json(&self) -> PolarsResult<String> {
+            let envelope = Envelope {
+                format: FORMAT,
+                version: VERSION,
+                model: self,
+            };
+            serde_json::to_string_pretty(&envelope).map_err(json_error)
+        }
+
+        /// Reads a model written by `to_json`, refusing other format versions
+        pub fn from_json(text: &str) -> PolarsResult<Model> {
+            let header: Header = serde_json::from_str(text).map_err(json_error)?;
+            polars_ensure!(
+                header.format.as_deref() == Some(FORMAT),
+                ComputeError: "not a decision tree model file"
+            );
+            match header.version {
+                Some(VERSION) => {}
+                Some(version) => polars_bail!(
+                    ComputeError: "model file version {} is not supported, this library reads version {}",
+                    version, VERSION
+                ),
+                None => polars_bail!(ComputeError: "model file has no format version"),
+            }
+            let content: Content = serde_json::from_str(text).map_err(json_error)?;
+            Ok(content.model)
+        }
+
+        pub fn save<P: AsRef<Path>>(&self, path: P) -> PolarsResult<()> {
+            fs::write(path, self.to_json()?)?;
+            Ok(())
+        }
+
+        pub fn load<P: AsRef<Path>>(path: P) -> PolarsResult<Model> {
+            Model::from_json(&fs::read_to_string(path)?)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::test::iris;
+        use crate::DTreeBuilder;
+        use std::collections::HashSet;
+
+        #[test]
+        fn json_round_trip() {
+            let data = iris();
+            let features = HashSet::from(["sepal_length", "sepal_width", "petal_length", "petal_width"]);
+            let model = DTreeBuilder::new(features, "variety")
+                .set_max_level(4)
+                .fit(&data)
+                .unwrap();
+            let loaded = Model::from_json(&model.to_json().unwrap()).unwrap();
+            assert_eq!(loaded.target, "variety");
+            assert_eq!(loaded.features, model.features);
+            assert_eq!(loaded.classes, vec!["Setosa", "Versicolor", "Virginica"]);
+            assert_eq!(loaded.parameters, model.parameters);
+            assert!(loaded.predict(&data).unwrap().equals(&model.predict(&data).unwrap()));
+            assert_eq!(loaded.to_json().unwrap(), model.to_json().unwrap());
+        }
+
+        #[test]
+        fn incompatible_version() {
+            let text = r#"{"format": "decision-tree", "version": 99, "model": {}}"#;
+            let error = Model::from_json(text).unwrap_err().to_string();
+            assert!(error.contains("version 99 is not supported"));
+        }
+    }
+}
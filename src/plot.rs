@@ -0,0 +1,171 @@
+use std::fmt;
+use std::fmt::Display;
+
+// colors assigned to classes in order of appearance
+const PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#59a14f", "#e15759", "#76b7b2", "#edc948", "#b07aa1", "#9c755f",
+];
+
+pub(crate) fn color(index: usize) -> &'static str {
+    PALETTE[index % PALETTE.len()]
+}
+
+// escapes text placed inside svg elements and attributes
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// a minimal svg chart mapping data coordinates into a framed area
+pub(crate) struct Plot {
+    width: f64,
+    height: f64,
+    margin: f64,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    elements: Vec<String>,
+}
+
+impl Plot {
+    pub(crate) fn new(x_range: (f64, f64), y_range: (f64, f64)) -> Plot {
+        // avoid a degenerate scale when all values coincide
+        let widen = |(low, high): (f64, f64)| if high > low { (low, high) } else { (low - 0.5, high + 0.5) };
+        Plot {
+            width: 640.0,
+            height: 480.0,
+            margin: 60.0,
+            x_range: widen(x_range),
+            y_range: widen(y_range),
+            elements: Vec::new(),
+        }
+    }
+
+    fn sx(&self, x: f64) -> f64 {
+        let (low, high) = self.x_range;
+        self.margin + (x - low) / (high - low) * (self.width - 2.0 * self.margin)
+    }
+
+    fn sy(&self, y: f64) -> f64 {
+        let (low, high) = self.y_range;
+        self.height - self.margin - (y - low) / (high - low) * (self.height - 2.0 * self.margin)
+    }
+
+    pub(crate) fn polyline(&mut self, points: &[(f64, f64)], stroke: &str, width: f64, opacity: f64) {
+        let coordinates: Vec<String> = points
+            .iter()
+            .map(|(x, y)| format!("{:.2},{:.2}", self.sx(*x), self.sy(*y)))
+            .collect();
+        self.elements.push(format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-opacity=\"{}\"/>",
+            coordinates.join(" "),
+            stroke,
+            width,
+            opacity
+        ));
+    }
+
+    pub(crate) fn rect(&mut self, lower: (f64, f64), upper: (f64, f64), fill: &str, opacity: f64) {
+        let (x0, x1) = (self.sx(lower.0), self.sx(upper.0));
+        let (y0, y1) = (self.sy(upper.1), self.sy(lower.1));
+        self.elements.push(format!(
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\" fill-opacity=\"{}\"/>",
+            x0,
+            y0,
+            x1 - x0,
+            y1 - y0,
+            fill,
+            opacity
+        ));
+    }
+
+    pub(crate) fn circle(&mut self, center: (f64, f64), radius: f64, fill: &str, opacity: f64) {
+        self.elements.push(format!(
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{}\" fill=\"{}\" fill-opacity=\"{}\" stroke=\"black\" stroke-width=\"0.5\"/>",
+            self.sx(center.0),
+            self.sy(center.1),
+            radius,
+            fill,
+            opacity
+        ));
+    }
+
+    // frame, ticks and axis titles
+    pub(crate) fn axes(&mut self, x_label: &str, y_label: &str) {
+        let (left, right) = (self.margin, self.width - self.margin);
+        let (top, bottom) = (self.margin, self.height - self.margin);
+        self.elements.push(format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"black\"/>",
+            left,
+            top,
+            right - left,
+            bottom - top
+        ));
+        let ticks = 5;
+        for i in 0..=ticks {
+            let share = i as f64 / ticks as f64;
+            let x = self.x_range.0 + share * (self.x_range.1 - self.x_range.0);
+            let y = self.y_range.0 + share * (self.y_range.1 - self.y_range.0);
+            self.elements.push(format!(
+                "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"11\" text-anchor=\"middle\">{:.2}</text>",
+                self.sx(x),
+                bottom + 16.0,
+                x
+            ));
+            self.elements.push(format!(
+                "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"11\" text-anchor=\"end\">{:.2}</text>",
+                left - 6.0,
+                self.sy(y) + 4.0,
+                y
+            ));
+        }
+        self.elements.push(format!(
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"13\" text-anchor=\"middle\">{}</text>",
+            (left + right) / 2.0,
+            self.height - 15.0,
+            escape(x_label)
+        ));
+        self.elements.push(format!(
+            "<text x=\"15\" y=\"{:.2}\" font-size=\"13\" text-anchor=\"middle\" transform=\"rotate(-90 15 {:.2})\">{}</text>",
+            (top + bottom) / 2.0,
+            (top + bottom) / 2.0,
+            escape(y_label)
+        ));
+    }
+
+    // color keys stacked in the top right corner
+    pub(crate) fn legend(&mut self, entries: &[(&str, &str)]) {
+        for (i, (fill, label)) in entries.iter().enumerate() {
+            let y = self.margin + 10.0 + 18.0 * i as f64;
+            let x = self.width - self.margin - 110.0;
+            self.elements.push(format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"12\" height=\"12\" fill=\"{}\"/>",
+                x,
+                y - 10.0,
+                fill
+            ));
+            self.elements.push(format!(
+                "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"12\">{}</text>",
+                x + 18.0,
+                y,
+                escape(label)
+            ));
+        }
+    }
+}
+
+impl Display for Plot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+            self.width, self.height
+        )?;
+        writeln!(f, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>")?;
+        for element in &self.elements {
+            writeln!(f, "{}", element)?;
+        }
+        write!(f, "</svg>")
+    }
+}
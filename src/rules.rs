@@ -0,0 +1,211 @@
+use crate::btree::{Node, Tree};
+use crate::Decision;
+use polars::prelude::*;
+use std::fmt;
+use std::fmt::{Display, Write};
+
+/// Interval of a feature allowed by a rule: `lower < feature <= upper`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub feature: String,
+    pub lower: Option<f64>,
+    pub upper: Option<f64>,
+}
+
+// thresholds are written in full unless a precision is given, as in `{:.2}`
+fn threshold(f: &fmt::Formatter, value: f64) -> String {
+    match f.precision() {
+        Some(decimals) => format!("{:.*}", decimals, value),
+        None => value.to_string(),
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.lower, self.upper) {
+            (Some(lower), Some(upper)) => write!(f, "{} < {} <= {}", threshold(f, lower), self.feature, threshold(f, upper)),
+            (Some(lower), None) => write!(f, "{} > {}", self.feature, threshold(f, lower)),
+            (None, Some(upper)) => write!(f, "{} <= {}", self.feature, threshold(f, upper)),
+            (None, None) => write!(f, "{} is any", self.feature),
+        }
+    }
+}
+
+/// A root to leaf path of the tree as a conjunction of conditions
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecisionRule {
+    // heap id of the leaf, as in `PreOrderTraversalIter`
+    pub leaf: usize,
+    pub conditions: Vec<Condition>,
+    pub prediction: String,
+    pub samples: usize,
+    // share of the training samples reaching the leaf
+    pub coverage: f64,
+    // share of the covered samples belonging to the predicted class
+    pub accuracy: f64,
+}
+
+impl Display for DecisionRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let conditions: Vec<String> = match f.precision() {
+            Some(decimals) => self.conditions.iter().map(|c| format!("{:.*}", decimals, c)).collect(),
+            None => self.conditions.iter().map(|c| c.to_string()).collect(),
+        };
+        if conditions.is_empty() {
+            write!(f, "IF true THEN {}", self.prediction)
+        } else {
+            write!(f, "IF {} THEN {}", conditions.join(" AND "), self.prediction)
+        }
+    }
+}
+
+// tightens the interval of a feature, adding it when first met
+fn restrict(conditions: &[Condition], feature: &str, lower: Option<f64>, upper: Option<f64>) -> Vec<Condition> {
+    let mut restricted = conditions.to_vec();
+    match restricted.iter_mut().find(|c| c.feature == feature) {
+        Some(condition) => {
+            condition.lower = match (condition.lower, lower) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            condition.upper = match (condition.upper, upper) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+        None => restricted.push(Condition {
+            feature: feature.to_string(),
+            lower,
+            upper,
+        }),
+    }
+    restricted
+}
+
+fn collect_rules(node: &Node<Decision>, id: usize, conditions: Vec<Condition>, total: f64, rules: &mut Vec<DecisionRule>) {
+    match (node.value.rule(), &node.left, &node.right) {
+        (Some(rule), Some(left), Some(right)) => {
+            let lower = restrict(&conditions, rule.dimension(), None, Some(rule.cutoff()));
+            collect_rules(right, (id << 1) + 1, lower, total, rules);
+            let higher = restrict(&conditions, rule.dimension(), Some(rule.cutoff()), None);
+            collect_rules(left, id << 1, higher, total, rules);
+        }
+        _ => rules.push(DecisionRule {
+            leaf: id,
+            conditions,
+            prediction: node.value.prediction().to_string(),
+            samples: node.value.samples(),
+            coverage: node.value.samples() as f64 / total,
+            accuracy: node.value.confidence(),
+        }),
+    }
+}
+
+impl Tree<Decision> {
+    /// Every root to leaf path as a rule, conditions on the same feature
+    /// merged into one interval; rules are sorted by decreasing coverage
+    pub fn rules(&self) -> Vec<DecisionRule> {
+        let mut rules = Vec::new();
+        if let Some(root) = self.root() {
+            let total = root.value.samples().max(1) as f64;
+            collect_rules(root, 1, Vec::new(), total, &mut rules);
+        }
+        rules.sort_by(|a, b| b.coverage.total_cmp(&a.coverage).then(b.accuracy.total_cmp(&a.accuracy)));
+        rules
+    }
+}
+
+/// Rules as a dataframe, one row per condition with its bounds as numbers;
+/// a rule without conditions gets a single row with null bounds
+pub fn rules_frame(rules: &[DecisionRule]) -> PolarsResult<DataFrame> {
+    // each rule with each of its conditions, or with none
+    let rows: Vec<(&DecisionRule, Option<&Condition>)> = rules
+        .iter()
+        .flat_map(|r| {
+            let conditions: Vec<Option<&Condition>> = if r.conditions.is_empty() {
+                vec![None]
+            } else {
+                r.conditions.iter().map(Some).collect()
+            };
+            conditions.into_iter().map(move |c| (r, c))
+        })
+        .collect();
+    df!(
+        "leaf" => rows.iter().map(|(r, _)| r.leaf as u64).collect::<Vec<_>>(),
+        "feature" => rows.iter().map(|(_, c)| c.map(|c| c.feature.as_str())).collect::<Vec<_>>(),
+        "lower" => rows.iter().map(|(_, c)| c.and_then(|c| c.lower)).collect::<Vec<_>>(),
+        "upper" => rows.iter().map(|(_, c)| c.and_then(|c| c.upper)).collect::<Vec<_>>(),
+        "prediction" => rows.iter().map(|(r, _)| r.prediction.as_str()).collect::<Vec<_>>(),
+        "samples" => rows.iter().map(|(r, _)| r.samples as u64).collect::<Vec<_>>(),
+        "coverage" => rows.iter().map(|(r, _)| r.coverage).collect::<Vec<_>>(),
+        "accuracy" => rows.iter().map(|(r, _)| r.accuracy).collect::<Vec<_>>(),
+    )
+}
+
+/// Rules as a Markdown table, thresholds rounded to `decimals` places
+pub fn rules_markdown(rules: &[DecisionRule], decimals: usize) -> String {
+    let mut text = String::from("| # | rule | samples | coverage | accuracy |\n|---|---|---|---|---|\n");
+    for (i, rule) in rules.iter().enumerate() {
+        let _ = writeln!(
+            text,
+            "| {} | {} | {} | {:.1}% | {:.1}% |",
+            i + 1,
+            format!("{:.*}", decimals, rule).replace('|', "\\|"),
+            rule.samples,
+            100.0 * rule.coverage,
+            100.0 * rule.accuracy
+        );
+    }
+    text
+}
+
+/// Rules as comma separated values with a header line
+pub fn rules_csv(rules: &[DecisionRule]) -> PolarsResult<String> {
+    let mut frame = rules_frame(rules)?;
+    let mut buffer: Vec<u8> = Vec::new();
+    CsvWriter::new(&mut buffer).include_header(true).finish(&mut frame)?;
+    String::from_utf8(buffer).map_err(|e| polars_err!(ComputeError: "{}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use std::collections::HashSet;
+
+    #[test]
+    fn rules_merge_intervals() {
+        let data = iris();
+        let tree = DTreeBuilder::new(HashSet::from(["petal_length"]), "variety")
+            .set_max_level(3)
+            .build(&data)
+            .unwrap();
+        let rules = tree.rules();
+        assert_eq!(rules.len(), tree.pre_order_iter().filter(|item| item.leaf).count());
+        let coverage: f64 = rules.iter().map(|r| r.coverage).sum();
+        assert!((coverage - 1.0).abs() < 1e-9);
+        // a single feature always collapses into a single interval
+        assert!(rules.iter().all(|r| r.conditions.len() == 1));
+        assert!(rules
+            .iter()
+            .any(|r| format!("{:.2}", r).starts_with("IF 2.45 < petal_length <= ")));
+
+        let frame = rules_frame(&rules).unwrap();
+        assert_eq!(frame.height(), rules.len());
+        // bounds keep the exact thresholds of the tree
+        let cutoffs: Vec<f64> = tree.pre_order_iter().filter_map(|item| item.value.rule()).map(|r| r.cutoff()).collect();
+        let lower = frame.column("lower").unwrap().f64().unwrap();
+        let upper = frame.column("upper").unwrap().f64().unwrap();
+        assert!(lower.into_iter().chain(upper).flatten().all(|b| cutoffs.contains(&b)));
+        assert_eq!(rules_markdown(&rules, 2).lines().count(), rules.len() + 2);
+        assert!(rules_markdown(&rules, 2).contains("| IF 2.45 < petal_length <= "));
+        assert!(rules_csv(&rules).unwrap().starts_with("leaf,feature,lower,upper,prediction,samples,coverage,accuracy\n"));
+
+        let features = HashSet::from(["petal_length", "petal_width"]);
+        let rules = DTreeBuilder::new(features, "variety").set_max_level(3).build(&data).unwrap().rules();
+        let conditions: usize = rules.iter().map(|r| r.conditions.len()).sum();
+        assert!(conditions > rules.len());
+        assert_eq!(rules_frame(&rules).unwrap().height(), conditions);
+    }
+}
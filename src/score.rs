@@ -0,0 +1,279 @@
+use crate::data::Format;
+use crate::model::Model;
+use polars::prelude::*;
+use std::io::{BufRead, Cursor, Write};
+
+/// Settings of `score_stream`
+#[derive(Debug, Clone)]
+pub struct ScoreOptions {
+    // `Format::Csv` or `Format::NdJson`, used for both input and output
+    pub format: Format,
+    // records read before scoring and writing a batch
+    pub chunk_size: usize,
+    // column copied from each input record to its prediction
+    pub id: Option<String>,
+    // add one column per class with its probability
+    pub probabilities: bool,
+}
+
+impl Default for ScoreOptions {
+    fn default() -> Self {
+        ScoreOptions {
+            format: Format::NdJson,
+            chunk_size: 1000,
+            id: None,
+            probabilities: false,
+        }
+    }
+}
+
+fn read_chunk(records: &str, format: Format, schema: Option<SchemaRef>) -> PolarsResult<DataFrame> {
+    let input = Cursor::new(records.as_bytes());
+    match (format, schema) {
+        (Format::Csv, schema) => CsvReader::new(input).has_header(true).with_schema(schema).finish(),
+        (_, Some(schema)) => JsonReader::new(input)
+            .with_json_format(JsonFormat::JsonLines)
+            .with_schema(schema)
+            .finish(),
+        (_, None) => JsonReader::new(input).with_json_format(JsonFormat::JsonLines).finish(),
+    }
+}
+
+// column types shared by every chunk, so that a chunk without any value
+// for a feature still has the column: features are numbers, the id keeps
+// the type inferred from the first chunk and CSV columns that are not used
+// are read as text
+fn chunk_schema(model: &Model, first: &str, options: &ScoreOptions) -> PolarsResult<SchemaRef> {
+    let inferred = read_chunk(first, options.format, None)?;
+    let id = options.id.as_ref().map(|id| {
+        let dtype = match inferred.column(id).map(|c| c.dtype().clone()) {
+            Ok(DataType::Null) | Err(_) => DataType::String,
+            Ok(dtype) => dtype,
+        };
+        (id.as_str(), dtype)
+    });
+    let dtype = |name: &str| match &id {
+        Some((id, dtype)) if *id == name => dtype.clone(),
+        _ if model.features.iter().any(|f| f == name) => DataType::Float64,
+        _ => DataType::String,
+    };
+    let names: Vec<&str> = match options.format {
+        // a CSV schema lists every column of the header
+        Format::Csv => inferred.get_column_names(),
+        _ => model.features.iter().map(|f| f.as_str()).chain(id.as_ref().map(|(id, _)| *id)).collect(),
+    };
+    Ok(Arc::new(Schema::from_iter(names.into_iter().map(|name| Field::new(name, dtype(name))))))
+}
+
+fn score_chunk<W: Write>(
+    model: &Model,
+    records: &str,
+    options: &ScoreOptions,
+    schema: &SchemaRef,
+    output: &mut W,
+    first: bool,
+) -> PolarsResult<()> {
+    let data = read_chunk(records, options.format, Some(schema.clone()))?;
+    let mut columns = Vec::new();
+    if let Some(id) = &options.id {
+        columns.push(
+            data.column(id)
+                .map_err(|_| polars_err!(ColumnNotFound: "id column `{}` not found", id))?
+                .clone(),
+        );
+    }
+    columns.push(model.predict(&data)?);
+    if options.probabilities {
+        columns.extend(model.tree.predict_proba(&data)?.get_columns().iter().cloned());
+    }
+    let mut scores = DataFrame::new(columns)?;
+    match options.format {
+        Format::Csv => CsvWriter::new(&mut *output).include_header(first).finish(&mut scores)?,
+        _ => JsonWriter::new(&mut *output)
+            .with_json_format(JsonFormat::JsonLines)
+            .finish(&mut scores)?,
+    }
+    output.flush()?;
+    Ok(())
+}
+
+/// Scores CSV or newline delimited JSON records read from `input` in
+/// chunks, writing one prediction per record to `output` in the same
+/// format; only one chunk is held in memory at a time. Returns the number
+/// of scored records.
+///
+/// Records are split on newlines, so CSV fields must not contain line breaks.
+pub fn score_stream<R: BufRead, W: Write>(
+    model: &Model,
+    input: R,
+    output: &mut W,
+    options: &ScoreOptions,
+) -> PolarsResult<usize> {
+    polars_ensure!(
+        matches!(options.format, Format::Csv | Format::NdJson),
+        ComputeError: "streaming supports CSV and NDJSON records, not {:?}", options.format
+    );
+    polars_ensure!(options.chunk_size > 0, ComputeError: "the chunk size must be positive");
+    let mut lines = input.lines();
+    // CSV chunks are parsed on their own, each needs the header line
+    let header = match options.format {
+        Format::Csv => match lines.next() {
+            Some(line) => line? + "\n",
+            None => return Ok(0),
+        },
+        _ => String::new(),
+    };
+    let mut chunk = header.clone();
+    let mut pending = 0;
+    let mut total = 0;
+    let mut schema: Option<SchemaRef> = None;
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        chunk.push_str(&line);
+        chunk.push('\n');
+        pending += 1;
+        if pending == options.chunk_size {
+            let schema = match &schema {
+                Some(schema) => schema,
+                None => schema.insert(chunk_schema(model, &chunk, options)?),
+            };
+            score_chunk(model, &chunk, options, schema, output, total == 0)?;
+            total += pending;
+            pending = 0;
+            chunk.clone_from(&header);
+        }
+    }
+    if pending > 0 {
+        let schema = match &schema {
+            Some(schema) => schema,
+            None => schema.insert(chunk_schema(model, &chunk, options)?),
+        };
+        score_chunk(model, &chunk, options, schema, output, total == 0)?;
+        total += pending;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+
+    #[test]
+    fn chunks_match_batch_predictions() {
+        let mut data = iris();
+        let features = ["sepal_length", "sepal_width", "petal_length", "petal_width"];
+        let model = DTreeBuilder::new(features, "variety")
+            .set_max_level(3)
+            .fit(&data)
+            .unwrap();
+        let expected: Vec<String> = model
+            .predict(&data)
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_iter()
+            .map(|p| p.unwrap().to_string())
+            .collect();
+        data.with_column(Series::new("id", (0..data.height() as u32).collect::<Vec<_>>()))
+            .unwrap();
+        data.try_apply("variety", |s| s.cast(&DataType::String)).unwrap();
+
+        let mut csv = Vec::new();
+        CsvWriter::new(&mut csv).finish(&mut data).unwrap();
+        let options = ScoreOptions {
+            format: Format::Csv,
+            chunk_size: 7,
+            id: Some("id".into()),
+            probabilities: true,
+        };
+        let mut output = Vec::new();
+        assert_eq!(score_stream(&model, csv.as_slice(), &mut output, &options).unwrap(), 150);
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 151);
+        assert_eq!(lines[0], "id,prediction,Setosa,Versicolor,Virginica");
+        for (i, line) in lines[1..].iter().enumerate() {
+            assert!(line.starts_with(&format!("{},{},", i, expected[i])));
+        }
+
+        let mut ndjson = Vec::new();
+        JsonWriter::new(&mut ndjson)
+            .with_json_format(JsonFormat::JsonLines)
+            .finish(&mut data)
+            .unwrap();
+        let options = ScoreOptions {
+            chunk_size: 64,
+            id: Some("id".into()),
+            ..ScoreOptions::default()
+        };
+        let mut output = Vec::new();
+        assert_eq!(score_stream(&model, ndjson.as_slice(), &mut output, &options).unwrap(), 150);
+        let text = String::from_utf8(output).unwrap();
+        assert_eq!(text.lines().count(), 150);
+        assert_eq!(text.lines().last().unwrap(), format!(r#"{{"id":149,"prediction":"{}"}}"#, expected[149]));
+    }
+
+    #[test]
+    fn chunk_without_a_feature() {
+        let data = iris();
+        let features = ["sepal_length", "sepal_width", "petal_length", "petal_width"];
+        let model = DTreeBuilder::new(features, "variety")
+            .set_max_level(3)
+            .fit(&data)
+            .unwrap();
+        // the second chunk has no petal width and no id at all
+        let ndjson = concat!(
+            r#"{"id": "a", "sepal_length": 5.1, "sepal_width": 3.5, "petal_length": 1.4, "petal_width": 0.2}"#,
+            "\n",
+            r#"{"id": "b", "sepal_length": 6.3, "sepal_width": 3.3, "petal_length": 6.0, "petal_width": 2.5}"#,
+            "\n",
+            r#"{"sepal_length": 5.9, "sepal_width": 3.0, "petal_length": 5.1, "extra": true}"#,
+            "\n",
+            r#"{"sepal_length": 5.0, "sepal_width": 3.4, "petal_length": 1.5}"#,
+            "\n",
+        );
+        let options = ScoreOptions {
+            chunk_size: 2,
+            id: Some("id".into()),
+            ..ScoreOptions::default()
+        };
+        let mut output = Vec::new();
+        assert_eq!(score_stream(&model, ndjson.as_bytes(), &mut output, &options).unwrap(), 4);
+        let rows = df!(
+            "sepal_length" => [5.1, 6.3, 5.9, 5.0],
+            "sepal_width" => [3.5, 3.3, 3.0, 3.4],
+            "petal_length" => [1.4, 6.0, 5.1, 1.5],
+            "petal_width" => [Some(0.2), Some(2.5), None, None],
+        )
+        .unwrap();
+        let expected = model.predict(&rows).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        for (i, (line, id)) in lines.iter().zip([r#""a""#, r#""b""#, "null", "null"]).enumerate() {
+            let prediction = expected.str().unwrap().get(i).unwrap();
+            assert_eq!(*line, format!(r#"{{"id":{},"prediction":"{}"}}"#, id, prediction));
+        }
+
+        let csv = "id,sepal_length,sepal_width,petal_length,petal_width\n\
+                   1,5.1,3.5,1.4,0.2\n2,6.3,3.3,6.0,2.5\n3,5.9,3.0,5.1,\n4,5.0,3.4,1.5,\n";
+        let options = ScoreOptions {
+            format: Format::Csv,
+            ..options
+        };
+        let mut output = Vec::new();
+        assert_eq!(score_stream(&model, csv.as_bytes(), &mut output, &options).unwrap(), 4);
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "id,prediction");
+        for (i, line) in lines[1..].iter().enumerate() {
+            let prediction = expected.str().unwrap().get(i).unwrap();
+            assert_eq!(*line, format!("{},{}", i + 1, prediction));
+        }
+    }
+}
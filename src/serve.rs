@@ -0,0 +1,245 @@
+use crate::model::Model;
+use polars::prelude::*;
+use serde_json::{json, Map, Value};
+use std::io::Read;
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Largest request body read by default, in bytes
+pub const MAX_BODY: usize = 1 << 20;
+
+/// Answers prediction requests for a single model over HTTP
+///
+/// - `GET /health` reports that the server is up
+/// - `GET /model` describes the target, features, classes and training configuration
+/// - `POST /predict` scores a JSON object, or an array of objects, mapping
+///   feature names to numbers or `null`; `?paths=true` adds the heap ids of
+///   the visited nodes to each prediction
+///
+/// Bodies longer than the limit of `set_max_body` are refused with 413.
+pub struct PredictionServer {
+    model: Model,
+    max_body: usize,
+}
+
+/// Stops a server started with `PredictionServer::spawn`
+pub struct StopHandle {
+    server: Arc<Server>,
+    thread: JoinHandle<()>,
+}
+
+impl StopHandle {
+    /// Stops accepting requests and waits for the server thread to finish
+    pub fn stop(self) -> PolarsResult<()> {
+        self.server.unblock();
+        self.thread
+            .join()
+            .map_err(|_| polars_err!(ComputeError: "the server thread panicked"))
+    }
+}
+
+fn error(status: u16, message: &str) -> (u16, Value) {
+    (status, json!({ "error": message }))
+}
+
+impl PredictionServer {
+    pub fn new(model: Model) -> PredictionServer {
+        PredictionServer { model, max_body: MAX_BODY }
+    }
+
+    pub fn set_max_body(mut self, max_body: usize) -> PredictionServer {
+        self.max_body = max_body;
+        self
+    }
+
+    fn metadata(&self) -> Value {
+        json!({
+            "target": self.model.target,
+            "features": self.model.features,
+            "classes": self.model.classes,
+            "parameters": self.model.parameters,
+            "config": self.model.config,
+        })
+    }
+
+    fn predict_row(&self, row: &Value, paths: bool) -> Result<Value, String> {
+        let row = row.as_object().ok_or("each row must be a JSON object")?;
+        let mut values: Map<String, Value> = Map::new();
+        for feature in &self.model.features {
+            match row.get(feature) {
+                Some(value) if value.is_number() || value.is_null() => {
+                    values.insert(feature.clone(), value.clone());
+                }
+                Some(_) => return Err(format!("feature `{}` must be a number or null", feature)),
+                None => return Err(format!("feature `{}` is missing", feature)),
+            }
+        }
+        let value = |name: &str| values.get(name).and_then(|v| v.as_f64());
+        let decision = self.model.tree.leaf(value).ok_or("the model has an empty tree")?;
+        let probabilities: Map<String, Value> = self
+            .model
+            .classes
+            .iter()
+            .map(|class| (class.clone(), json!(decision.probability(class))))
+            .collect();
+        let mut prediction = json!({
+            "label": decision.prediction(),
+            "probabilities": probabilities,
+        });
+        if paths {
+            prediction["path"] = json!(self.model.tree.decision_path(value));
+        }
+        Ok(prediction)
+    }
+
+    fn predict(&self, body: &str, paths: bool) -> (u16, Value) {
+        let request: Value = match serde_json::from_str(body) {
+            Ok(request) => request,
+            Err(e) => return error(400, &format!("invalid JSON: {}", e)),
+        };
+        let result = match &request {
+            Value::Array(rows) => rows
+                .iter()
+                .map(|row| self.predict_row(row, paths))
+                .collect::<Result<Vec<Value>, String>>()
+                .map(Value::Array),
+            row => self.predict_row(row, paths),
+        };
+        match result {
+            Ok(response) => (200, response),
+            Err(message) => error(400, &message),
+        }
+    }
+
+    /// Status code and JSON body answering a request
+    pub fn handle(&self, method: &str, url: &str, body: &str) -> (u16, Value) {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let paths = query.split('&').any(|p| p == "paths=true" || p == "paths=1");
+        match (method, path) {
+            ("GET", "/health") => (200, json!({ "status": "ok" })),
+            ("GET", "/model") => (200, self.metadata()),
+            ("POST", "/predict") => self.predict(body, paths),
+            (_, "/health") | (_, "/model") | (_, "/predict") => error(405, "method not allowed"),
+            _ => error(404, "not found"),
+        }
+    }
+
+    // reads at most one byte past the limit, so that an oversized body
+    // without a declared length is still detected
+    fn respond(&self, request: &mut Request) -> (u16, Value) {
+        if request.body_length().is_some_and(|length| length > self.max_body) {
+            return error(413, "request body too large");
+        }
+        let mut body = String::new();
+        let limit = self.max_body as u64 + 1;
+        if let Err(e) = request.as_reader().take(limit).read_to_string(&mut body) {
+            return error(400, &format!("cannot read the request body: {}", e));
+        }
+        if body.len() > self.max_body {
+            return error(413, "request body too large");
+        }
+        let method = match request.method() {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            _ => "OTHER",
+        };
+        self.handle(method, request.url(), &body)
+    }
+
+    fn serve(&self, server: &Server) {
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        for mut request in server.incoming_requests() {
+            let (status, response) = self.respond(&mut request);
+            let response = Response::from_string(response.to_string())
+                .with_status_code(status)
+                .with_header(content_type.clone());
+            // a client hanging up must not stop the server
+            let _ = request.respond(response);
+        }
+    }
+
+    /// Serves requests one at a time until the listener fails
+    pub fn run(&self, listener: TcpListener) -> PolarsResult<()> {
+        let server = Server::from_listener(listener, None)
+            .map_err(|e| polars_err!(ComputeError: "cannot start the server: {}", e))?;
+        self.serve(&server);
+        Ok(())
+    }
+
+    /// Serves requests on a background thread until the returned handle is stopped
+    pub fn spawn(self, listener: TcpListener) -> PolarsResult<StopHandle> {
+        let server = Server::from_listener(listener, None)
+            .map_err(|e| polars_err!(ComputeError: "cannot start the server: {}", e))?;
+        let server = Arc::new(server);
+        let shared = Arc::clone(&server);
+        let thread = std::thread::spawn(move || self.serve(&shared));
+        Ok(StopHandle { server, thread })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use std::io::Write;
+    use std::net::{SocketAddr, TcpStream};
+
+    fn send(address: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut answer = String::new();
+        stream.read_to_string(&mut answer).unwrap();
+        answer
+    }
+
+    #[test]
+    fn predictions_over_localhost() {
+        let features = ["sepal_length", "sepal_width", "petal_length", "petal_width"];
+        let model = DTreeBuilder::new(features, "variety")
+            .set_max_level(3)
+            .fit(&iris())
+            .unwrap();
+        let server = PredictionServer::new(model).set_max_body(256);
+
+        let row = r#"{"sepal_length": 5.1, "sepal_width": 3.5, "petal_length": 1.4, "petal_width": 0.2}"#;
+        let (status, response) = server.handle("POST", "/predict?paths=true", row);
+        assert_eq!(status, 200);
+        assert_eq!(response["label"], "Setosa");
+        assert_eq!(response["probabilities"]["Setosa"], 1.0);
+        assert_eq!(response["path"], json!([1, 3]));
+
+        let batch = format!("[{}, {}]", row, row.replace("1.4", "null"));
+        let (status, response) = server.handle("POST", "/predict", &batch);
+        assert_eq!(status, 200);
+        assert_eq!(response.as_array().unwrap().len(), 2);
+        assert!(response[0].get("path").is_none());
+
+        assert_eq!(server.handle("POST", "/predict", r#"{"petal_length": 1.4}"#).0, 400);
+        assert_eq!(server.handle("POST", "/predict", "[1]").0, 400);
+        assert_eq!(server.handle("GET", "/predict", "").0, 405);
+        assert_eq!(server.handle("GET", "/model", "").1["classes"][2], "Virginica");
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let handle = server.spawn(listener).unwrap();
+        let answer = send(address, "GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+        assert!(answer.starts_with("HTTP/1.1 200"));
+        assert!(answer.ends_with(r#"{"status":"ok"}"#));
+
+        // four rows are well over the 256 byte limit
+        let large = format!("[{}]", [row; 4].join(","));
+        let answer = send(
+            address,
+            &format!(
+                "POST /predict HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                large.len(),
+                large
+            ),
+        );
+        assert!(answer.starts_with("HTTP/1.1 413"));
+        handle.stop().unwrap();
+    }
+}
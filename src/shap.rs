@@ -0,0 +1,249 @@
+use crate::btree::{Node, Tree};
+use crate::Decision;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+// one step of the unique feature path followed by the recursion
+#[derive(Debug, Clone, Copy)]
+struct PathElement {
+    feature: Option<usize>,
+    zero_fraction: f64,
+    one_fraction: f64,
+    weight: f64,
+}
+
+// adds a feature to the path updating the permutation weights
+fn extend_path(path: &mut Vec<PathElement>, zero_fraction: f64, one_fraction: f64, feature: Option<usize>) {
+    let depth = path.len();
+    path.push(PathElement {
+        feature,
+        zero_fraction,
+        one_fraction,
+        weight: if depth == 0 { 1.0 } else { 0.0 },
+    });
+    for i in (0..depth).rev() {
+        path[i + 1].weight += one_fraction * path[i].weight * (i + 1) as f64 / (depth + 1) as f64;
+        path[i].weight = zero_fraction * path[i].weight * (depth - i) as f64 / (depth + 1) as f64;
+    }
+}
+
+// removes a feature from the path undoing its contribution to the weights
+fn unwind_path(path: &mut Vec<PathElement>, index: usize) {
+    let depth = path.len() - 1;
+    let one_fraction = path[index].one_fraction;
+    let zero_fraction = path[index].zero_fraction;
+    let mut next_one_portion = path[depth].weight;
+    for i in (0..depth).rev() {
+        if one_fraction != 0.0 {
+            let weight = path[i].weight;
+            path[i].weight = next_one_portion * (depth + 1) as f64 / ((i + 1) as f64 * one_fraction);
+            next_one_portion =
+                weight - path[i].weight * zero_fraction * (depth - i) as f64 / (depth + 1) as f64;
+        } else {
+            path[i].weight = path[i].weight * (depth + 1) as f64 / (zero_fraction * (depth - i) as f64);
+        }
+    }
+    for i in index..depth {
+        path[i].feature = path[i + 1].feature;
+        path[i].zero_fraction = path[i + 1].zero_fraction;
+        path[i].one_fraction = path[i + 1].one_fraction;
+    }
+    path.pop();
+}
+
+// total weight of the path as if the feature at `index` was unwound
+fn unwound_path_sum(path: &[PathElement], index: usize) -> f64 {
+    let depth = path.len() - 1;
+    let one_fraction = path[index].one_fraction;
+    let zero_fraction = path[index].zero_fraction;
+    let mut next_one_portion = path[depth].weight;
+    let mut total = 0.0;
+    for i in (0..depth).rev() {
+        if one_fraction != 0.0 {
+            let weight = next_one_portion * (depth + 1) as f64 / ((i + 1) as f64 * one_fraction);
+            total += weight;
+            next_one_portion =
+                path[i].weight - weight * zero_fraction * (depth - i) as f64 / (depth + 1) as f64;
+        } else {
+            total += path[i].weight / zero_fraction / ((depth - i) as f64 / (depth + 1) as f64);
+        }
+    }
+    total
+}
+
+// shared state of the explanation of a single row
+struct Explainer<'a> {
+    features: &'a HashMap<&'a str, usize>,
+    classes: &'a [String],
+    row: &'a [Option<f64>],
+    // contributions indexed by feature then by class
+    phi: Vec<Vec<f64>>,
+}
+
+impl<'a> Explainer<'a> {
+    fn recurse(
+        &mut self,
+        node: &Node<Decision>,
+        parent_path: &[PathElement],
+        zero_fraction: f64,
+        one_fraction: f64,
+        feature: Option<usize>,
+    ) {
+        let mut path = parent_path.to_vec();
+        extend_path(&mut path, zero_fraction, one_fraction, feature);
+        let (rule, left, right) = match (&node.value.rule, &node.left, &node.right) {
+            (Some(rule), Some(left), Some(right)) => (rule, left, right),
+            _ => {
+                // leaf: distribute the node value along the path
+                for i in 1..path.len() {
+                    let weight = unwound_path_sum(&path, i);
+                    let element = path[i];
+                    let scale = weight * (element.one_fraction - element.zero_fraction);
+                    if let Some(index) = element.feature {
+                        for (c, class) in self.classes.iter().enumerate() {
+                            self.phi[index][c] += scale * node.value.probability(class);
+                        }
+                    }
+                }
+                return;
+            }
+        };
+        let split = self.features[rule.dimension.as_str()];
+        let (hot, cold) = if self.row[split].is_some_and(|v| v > rule.cutoff) {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        let cover = node.value.samples as f64;
+        let hot_fraction = hot.value.samples as f64 / cover;
+        let cold_fraction = cold.value.samples as f64 / cover;
+
+        // a feature already met along the path is merged with the new split
+        let mut incoming_zero = 1.0;
+        let mut incoming_one = 1.0;
+        if let Some(index) = (1..path.len()).find(|&k| path[k].feature == Some(split)) {
+            incoming_zero = path[index].zero_fraction;
+            incoming_one = path[index].one_fraction;
+            unwind_path(&mut path, index);
+        }
+        self.recurse(hot, &path, hot_fraction * incoming_zero, incoming_one, Some(split));
+        self.recurse(cold, &path, cold_fraction * incoming_zero, 0.0, Some(split));
+    }
+}
+
+// prediction averaged over the training samples reaching each node
+fn expected_value(node: &Node<Decision>, classes: &[String]) -> Vec<f64> {
+    match (&node.left, &node.right) {
+        (Some(left), Some(right)) => {
+            let total = (left.value.samples + right.value.samples) as f64;
+            let higher = expected_value(left, classes);
+            let lower = expected_value(right, classes);
+            higher
+                .iter()
+                .zip(lower)
+                .map(|(h, l)| {
+                    (h * left.value.samples as f64 + l * right.value.samples as f64) / total
+                })
+                .collect()
+        }
+        _ => classes.iter().map(|class| node.value.probability(class)).collect(),
+    }
+}
+
+impl Tree<Decision> {
+    /// Exact TreeSHAP attributions of the class probabilities: one row for
+    /// each observation and class, with a column for each feature and the
+    /// expected value of the tree
+    pub fn shap_values(&self, data: &DataFrame, features: &[&str]) -> PolarsResult<DataFrame> {
+        let root = self
+            .root()
+            .ok_or_else(|| polars_err!(ComputeError: "cannot explain an empty tree"))?;
+        let index: HashMap<&str, usize> = features.iter().enumerate().map(|(i, f)| (*f, i)).collect();
+        for item in self.pre_order_iter() {
+            if let Some(ref rule) = item.value.rule {
+                polars_ensure!(
+                    index.contains_key(rule.dimension.as_str()),
+                    ColumnNotFound: "the tree splits on `{}` which is not among the explained features", rule.dimension
+                );
+            }
+        }
+        let classes = self.classes();
+        let expected = expected_value(root, &classes);
+
+        let columns: Vec<Vec<Option<f64>>> = features
+            .iter()
+            .map(|feature| {
+                Ok(data
+                    .column(feature)?
+                    .cast(&DataType::Float64)?
+                    .f64()?
+                    .into_iter()
+                    .collect())
+            })
+            .collect::<PolarsResult<_>>()?;
+
+        let mut rows: Vec<u32> = Vec::new();
+        let mut labels: Vec<&str> = Vec::new();
+        let mut values: Vec<Vec<f64>> = vec![Vec::new(); features.len()];
+        let mut baseline: Vec<f64> = Vec::new();
+        for row in 0..data.height() {
+            let observation: Vec<Option<f64>> = columns.iter().map(|c| c[row]).collect();
+            let mut explainer = Explainer {
+                features: &index,
+                classes: &classes,
+                row: &observation,
+                phi: vec![vec![0.0; classes.len()]; features.len()],
+            };
+            explainer.recurse(root, &[], 1.0, 1.0, None);
+            for (c, class) in classes.iter().enumerate() {
+                rows.push(row as u32);
+                labels.push(class);
+                for (f, contributions) in explainer.phi.iter().enumerate() {
+                    values[f].push(contributions[c]);
+                }
+                baseline.push(expected[c]);
+            }
+        }
+
+        let mut series = vec![Series::new("row", rows), Series::new("class", labels)];
+        for (feature, contributions) in features.iter().zip(values) {
+            series.push(Series::new(feature, contributions));
+        }
+        series.push(Series::new("expected_value", baseline));
+        DataFrame::new(series)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test::iris;
+    use crate::DTreeBuilder;
+    use std::collections::HashSet;
+
+    #[test]
+    fn shap_values_are_additive() {
+        let data = iris();
+        let features = ["sepal_length", "sepal_width", "petal_length", "petal_width"];
+        let tree = DTreeBuilder::new(HashSet::from(features), "variety")
+            .set_max_level(4)
+            .build(&data)
+            .unwrap();
+        let rows = data.slice(45, 60);
+        let shap = tree.shap_values(&rows, &features).unwrap();
+        assert_eq!(shap.height(), rows.height() * 3);
+
+        let columns = tree.feature_columns(&rows).unwrap();
+        for i in 0..shap.height() {
+            let row = shap.column("row").unwrap().u32().unwrap().get(i).unwrap() as usize;
+            let class = shap.column("class").unwrap().str().unwrap().get(i).unwrap();
+            let mut total = shap.column("expected_value").unwrap().f64().unwrap().get(i).unwrap();
+            for feature in features {
+                total += shap.column(feature).unwrap().f64().unwrap().get(i).unwrap();
+            }
+            let leaf = tree
+                .leaf(|feature| columns.get(feature).and_then(|c| c[row]))
+                .unwrap();
+            assert!((total - leaf.probability(class)).abs() < 1e-9);
+        }
+    }
+}